@@ -0,0 +1,204 @@
+// Typed async client for the fesghel API.
+// Requests and responses are the server's own types from `fesghel::request`
+// and `fesghel::response`, so both sides always agree on the JSON.
+//
+// let client = fesghel_client::Client::new("https://sho.rt").with_api_key("s3cret");
+// let link = client.create(&Url::new("https://example.com").with_name("docs")).await?;
+
+use std::fmt;
+
+use fesghel::client::API_KEY_HEADER;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+
+pub use fesghel::request::Url;
+pub use fesghel::response::{Details, Link, Problem, Shortened, Stats};
+
+#[derive(Debug)]
+pub enum Error {
+    // The request did not complete or the response could not be decoded.
+    Http(reqwest::Error),
+    // The server answered with an error status. `code` is the stable name of
+    // the error from its problem document, if the body was one.
+    Api {
+        status: StatusCode,
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Status of an API error, e.g. to tell a 404 from a 409.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Http(err) => err.status(),
+            Error::Api { status, .. } => Some(*status),
+        }
+    }
+
+    /// Machine-readable code of an API error, e.g. `duplicate_key`.
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Error::Http(_) => None,
+            Error::Api { code, .. } => code.as_deref(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "request failed: {}", err),
+            Error::Api {
+                status, message, ..
+            } => write!(f, "{}: {}", status, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(err) => Some(err),
+            Error::Api { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+// `reqwest::Client` pools connections and is cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+    api_key: Option<String>,
+}
+
+impl Client {
+    /// Client for the server at `base`, e.g. `https://sho.rt`.
+    pub fn new(base: &str) -> Self {
+        Client {
+            http: reqwest::Client::new(),
+            base: base.trim_end_matches('/').to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Identify with an API key issued in the server's `[api]` settings.
+    /// Links belong to the identity that created them, so without a key
+    /// only links created from the same IP address are visible.
+    pub fn with_api_key(mut self, key: &str) -> Self {
+        self.api_key = Some(key.to_string());
+        self
+    }
+
+    /// Use a preconfigured `reqwest::Client`, e.g. with timeouts or a proxy.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}/api{path}", self.base));
+        match &self.api_key {
+            Some(key) => request.header(API_KEY_HEADER, key),
+            None => request,
+        }
+    }
+
+    // Turn error statuses into `Error::Api`. Error bodies are problem
+    // documents; anything else, e.g. from a proxy, is passed on as text.
+    async fn send(request: RequestBuilder) -> Result<Response, Error> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await?;
+        Err(match serde_json::from_str::<Problem>(&body) {
+            Ok(problem) => Error::Api {
+                status,
+                code: Some(problem.code().to_string()),
+                message: problem.to_string(),
+            },
+            Err(_) => Error::Api {
+                status,
+                code: None,
+                message: body,
+            },
+        })
+    }
+
+    /// Create a link and return it with its key and short URL.
+    pub async fn create(&self, url: &Url) -> Result<Shortened, Error> {
+        let response = Self::send(self.request(Method::POST, "/urls").json(url)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Settings of one of the caller's links.
+    pub async fn details(&self, key: &str) -> Result<Details, Error> {
+        let response = Self::send(self.request(Method::GET, &format!("/urls/{key}"))).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Delete one of the caller's links.
+    pub async fn delete(&self, key: &str) -> Result<(), Error> {
+        Self::send(self.request(Method::DELETE, &format!("/urls/{key}"))).await?;
+        Ok(())
+    }
+
+    /// The caller's newest links, up to `limit` (server default 20, at most 1000).
+    pub async fn list(&self, limit: Option<u32>) -> Result<Vec<Link>, Error> {
+        let mut request = self.request(Method::GET, "/urls");
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        let response = Self::send(request).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Click totals of one of the caller's links.
+    pub async fn stats(&self, key: &str) -> Result<Stats, Error> {
+        let response = Self::send(self.request(Method::GET, &format!("/urls/{key}/stats"))).await?;
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_without_trailing_slash() {
+        let client = Client::new("https://sho.rt/");
+        let request = client.request(Method::GET, "/urls").build().unwrap();
+        assert_eq!(request.url().as_str(), "https://sho.rt/api/urls");
+        assert!(request.headers().get(API_KEY_HEADER).is_none());
+    }
+
+    #[test]
+    fn api_key_is_sent() {
+        let client = Client::new("http://localhost:1378").with_api_key("team-a");
+        let request = client.request(Method::DELETE, "/urls/abc").build().unwrap();
+        assert_eq!(request.headers()[API_KEY_HEADER], "team-a");
+        assert_eq!(request.method(), Method::DELETE);
+    }
+
+    #[test]
+    fn api_error_display() {
+        let err = Error::Api {
+            status: StatusCode::CONFLICT,
+            code: Some(String::from("duplicate_key")),
+            message: String::from("key already exists: abc"),
+        };
+        assert_eq!(err.to_string(), "409 Conflict: key already exists: abc");
+        assert_eq!(err.status(), Some(StatusCode::CONFLICT));
+        assert_eq!(err.code(), Some("duplicate_key"));
+    }
+}
@@ -0,0 +1,208 @@
+// Integration tests against the server running in-process.
+// They need MongoDB, at `FESGHEL_TEST_MONGO` or the address from
+// `docker compose up`, so they are ignored by default; run them with
+// `cargo test -- --include-ignored`, as CI does. Without MongoDB they fail.
+// Each test works in a database of its own, dropped at the end.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use actix_web::dev::ServerHandle;
+use actix_web::{App, HttpServer};
+use fesghel::{client, store};
+use fesghel_client::{Client, Url};
+use mongodb::Database;
+use mongodb::bson::doc;
+use mongodb::options::ClientOptions;
+
+const DEFAULT_MONGO: &str = "mongodb://127.0.0.1:27017";
+
+static DATABASES: AtomicUsize = AtomicUsize::new(0);
+
+// A fresh database. MongoDB gets a few seconds, since CI starts it right
+// before the tests.
+async fn database() -> Database {
+    let address = std::env::var("FESGHEL_TEST_MONGO").unwrap_or_else(|_| DEFAULT_MONGO.into());
+    let mut options = ClientOptions::parse(address).await.unwrap();
+    options.server_selection_timeout = Some(Duration::from_secs(30));
+    let client = mongodb::Client::with_options(options).unwrap();
+
+    let name = format!(
+        "fesghel_client_{}_{}",
+        std::process::id(),
+        DATABASES.fetch_add(1, Ordering::Relaxed)
+    );
+    let db = client.database(&name);
+    if let Err(err) = db.run_command(doc! { "ping": 1 }).await {
+        panic!("MongoDB is not reachable: {err}");
+    }
+    db
+}
+
+// Serve the API on a free local port and return its base URL.
+async fn serve(db: &Database) -> (String, ServerHandle) {
+    let service = fesghel::Builder::new(
+        store::Url::new(db.clone()).await,
+        store::Click::new(db.clone()).await,
+    )
+    .with_api_keys(client::ApiKeys::new([
+        ("team-a", "key-a"),
+        ("team-b", "key-b"),
+    ]))
+    .build();
+
+    let server = HttpServer::new(move || App::new().configure(|cfg| service.configure(cfg)))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let address = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+
+    (format!("http://{address}"), handle)
+}
+
+#[actix_web::test]
+#[ignore = "needs MongoDB"]
+async fn link_lifecycle() {
+    let db = database().await;
+    let (base, server) = serve(&db).await;
+    let client = Client::new(&base).with_api_key("key-a");
+
+    let created = client
+        .create(
+            &Url::new("https://example.com/docs")
+                .with_name("docs")
+                .with_max_clicks(5)
+                .with_window(None, Some("2100-01-01T00:00:00Z"))
+                .with_tags(vec![String::from("launch")]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.key(), "docs");
+    assert_eq!(created.short_url(), format!("{base}/api/docs"));
+    assert_eq!(created.url(), "https://example.com/docs");
+    assert!(created.created_at().is_some());
+    assert_eq!(created.expires_at(), Some("2100-01-01T00:00:00Z"));
+
+    let details = client.details("docs").await.unwrap();
+    assert_eq!(details.url(), "https://example.com/docs");
+    assert_eq!(details.max_clicks(), Some(5));
+    assert_eq!(details.tags(), ["launch"]);
+    assert!(details.created_at().is_some());
+    assert!(!details.protected());
+
+    let random = client
+        .create(&Url::new("https://example.com/b"))
+        .await
+        .unwrap();
+    let listed = client.list(None).await.unwrap();
+    let keys: Vec<&str> = listed.iter().map(|link| link.key()).collect();
+    assert_eq!(keys, [random.key(), "docs"]);
+    assert_eq!(client.list(Some(1)).await.unwrap().len(), 1);
+
+    let stats = client.stats("docs").await.unwrap();
+    assert_eq!(stats.clicks(), 0);
+
+    // Visit the link; clicks are recorded in the background.
+    let visitor = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = visitor
+        .post(format!("{base}/api/urls"))
+        .header("X-API-Key", "key-a")
+        .json(&serde_json::json!({ "url": "https://example.com", "name": "v1.2~beta" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 201);
+    assert_eq!(response.headers()["location"], "/api/urls/v1.2~beta");
+    let visit = visitor.get(created.short_url()).send().await.unwrap();
+    assert!(visit.status().is_redirection());
+    let mut clicks = 0;
+    for _ in 0..50 {
+        clicks = client.stats("docs").await.unwrap().clicks();
+        if clicks > 0 {
+            break;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(clicks, 1);
+
+    // Deleting a link takes its clicks along, so a new link under the same
+    // key starts from zero.
+    client.delete("docs").await.unwrap();
+    let err = client.details("docs").await.unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(404));
+    client
+        .create(&Url::new("https://example.com/other").with_name("docs"))
+        .await
+        .unwrap();
+    assert_eq!(client.stats("docs").await.unwrap().clicks(), 0);
+
+    server.stop(true).await;
+    db.drop().await.unwrap();
+}
+
+#[actix_web::test]
+#[ignore = "needs MongoDB"]
+async fn errors_carry_the_status() {
+    let db = database().await;
+    let (base, server) = serve(&db).await;
+    let client = Client::new(&base).with_api_key("key-a");
+
+    let err = client.create(&Url::new("not a url")).await.unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(400));
+    assert_eq!(err.code(), Some("invalid_url"));
+
+    client
+        .create(&Url::new("https://example.com").with_name("taken"))
+        .await
+        .unwrap();
+    let err = client
+        .create(&Url::new("https://example.com").with_name("taken"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(409));
+    assert_eq!(err.to_string(), "409 Conflict: key already exists: taken");
+    assert_eq!(err.code(), Some("duplicate_key"));
+
+    server.stop(true).await;
+    db.drop().await.unwrap();
+}
+
+#[actix_web::test]
+#[ignore = "needs MongoDB"]
+async fn links_of_others_are_hidden() {
+    let db = database().await;
+    let (base, server) = serve(&db).await;
+    let owner = Client::new(&base).with_api_key("key-a");
+    let other = Client::new(&base).with_api_key("key-b");
+
+    owner
+        .create(&Url::new("https://example.com").with_name("mine"))
+        .await
+        .unwrap();
+
+    for err in [
+        other.details("mine").await.unwrap_err(),
+        other.stats("mine").await.unwrap_err(),
+        other.delete("mine").await.unwrap_err(),
+    ] {
+        assert_eq!(err.status().map(|s| s.as_u16()), Some(404));
+        assert_eq!(err.code(), Some("not_found"));
+    }
+    assert!(other.list(None).await.unwrap().is_empty());
+    assert!(owner.details("mine").await.is_ok());
+
+    // Without an issued key, links cannot be managed at all.
+    let anonymous = Client::new(&base);
+    let err = anonymous.details("mine").await.unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(401));
+    assert_eq!(err.code(), Some("unauthorized"));
+
+    server.stop(true).await;
+    db.drop().await.unwrap();
+}
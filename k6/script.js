@@ -36,12 +36,12 @@ export default function () {
       });
 
       const success = check(res, {
-        'status is 200': (r) => r.status === 200,
+        'status is 201': (r) => r.status === 201,
         'response time < 200ms': (r) => r.timings.duration < 200,
       });
 
       if (success) {
-        name = res.json();
+        name = res.json('key');
       }
     });
 
@@ -0,0 +1,281 @@
+// Link management commands.
+//   fesghel create <url> [--name <key>] [--owner <name>]
+//   fesghel get <key>
+//   fesghel delete <key>
+//   fesghel list [--owner <name>] [--limit <n>]
+//   fesghel migrate
+
+use std::io;
+
+use mongodb::bson;
+
+use super::value;
+use crate::model;
+use crate::request;
+use crate::service;
+use crate::store;
+
+// Owner of links created from the command line unless another one is given.
+const OWNER: &str = "cli";
+// Links shown by `list` unless `--limit` says otherwise.
+const LIST_LIMIT: i64 = 20;
+
+// `store::Error` boxes a non-`Send` error, so only its message is kept.
+fn failed(err: store::Error) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+fn not_found(key: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("no link with key {key}"))
+}
+
+#[derive(Debug, PartialEq)]
+struct CreateArgs {
+    url: String,
+    name: Option<String>,
+    owner: String,
+}
+
+fn parse_create(args: &[String]) -> Result<CreateArgs, String> {
+    let mut url = None;
+    let mut name = None;
+    let mut owner = String::from(OWNER);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = Some(value(&mut args, arg)?),
+            "--owner" => owner = value(&mut args, arg)?,
+            _ if url.is_none() && !arg.starts_with("--") => url = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    let url = url.ok_or("usage: fesghel create <url> [--name <key>] [--owner <name>]")?;
+    Ok(CreateArgs { url, name, owner })
+}
+
+// The one argument of `get` and `delete`.
+fn parse_key<'a>(command: &str, args: &'a [String]) -> Result<&'a str, String> {
+    match args {
+        [key] if !key.starts_with("--") => Ok(key),
+        _ => Err(format!("usage: fesghel {command} <key>")),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ListArgs {
+    owner: Option<String>,
+    limit: i64,
+}
+
+fn parse_list(args: &[String]) -> Result<ListArgs, String> {
+    let mut parsed = ListArgs {
+        owner: None,
+        limit: LIST_LIMIT,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--owner" => parsed.owner = Some(value(&mut args, arg)?),
+            "--limit" => {
+                parsed.limit = value(&mut args, arg)?
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or("--limit needs a positive number")?
+            }
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Where the server answers short links: the public URL and the path the
+/// service is mounted under. Without a public URL only the key can be
+/// printed, since the host is unknown here.
+pub struct Base<'a> {
+    public_url: Option<&'a str>,
+    mount_path: &'a str,
+}
+
+impl<'a> Base<'a> {
+    pub fn new(public_url: Option<&'a str>, mount_path: &'a str) -> Self {
+        Base {
+            public_url,
+            mount_path,
+        }
+    }
+
+    // Same shape as the server's short URLs, which sit in the API scope.
+    fn short_url(&self, key: &str) -> String {
+        match self.public_url {
+            Some(url) => format!(
+                "{}{}{}/{key}",
+                url.trim_end_matches('/'),
+                self.mount_path.trim_end_matches('/'),
+                service::API_SCOPE
+            ),
+            None => key.to_string(),
+        }
+    }
+}
+
+/// Create a link, validated like an API request, and print its short URL.
+pub async fn create(store: store::Url, base: Base<'_>, args: &[String]) -> io::Result<()> {
+    let args = parse_create(args).map_err(io::Error::other)?;
+
+    // Going through `request::Url` applies the API's validation and defaults.
+    let mut url = request::Url::new(&args.url);
+    if let Some(name) = &args.name {
+        url = url.with_name(name);
+    }
+    url.validate()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+
+    let link = url.to_model(&url.key(), &args.owner);
+    store.store(&link).await.map_err(failed)?;
+    println!("{}", base.short_url(link.key()));
+    Ok(())
+}
+
+/// Print a stored link as JSON.
+pub async fn get(store: store::Url, args: &[String]) -> io::Result<()> {
+    let key = parse_key("get", args).map_err(io::Error::other)?;
+    let link = store.fetch(key).await.ok_or_else(|| not_found(key))?;
+
+    // Relaxed extended JSON shows dates as `{"$date": "..."}`, like the export.
+    let doc = bson::to_bson(&link).map_err(io::Error::other)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&doc.into_relaxed_extjson()).map_err(io::Error::other)?
+    );
+    Ok(())
+}
+
+/// Delete a link together with its recorded clicks.
+pub async fn delete(store: store::Url, clicks: store::Click, args: &[String]) -> io::Result<()> {
+    let key = parse_key("delete", args).map_err(io::Error::other)?;
+    if !store.delete(key).await.map_err(failed)? {
+        return Err(not_found(key));
+    }
+    let deleted = clicks.delete_key(key).await.map_err(failed)?;
+    println!("deleted {key} and its {deleted} clicks");
+    Ok(())
+}
+
+// One tab-separated line per link, so the output can be piped to `cut`.
+fn list_line(link: &model::Url) -> String {
+    let created_at = link
+        .created_at()
+        .and_then(|at| at.try_to_rfc3339_string().ok())
+        .unwrap_or_else(|| String::from("-"));
+    format!("{}\t{created_at}\t{}", link.key(), link.url())
+}
+
+/// Print the newest links.
+pub async fn list(store: store::Url, args: &[String]) -> io::Result<()> {
+    let args = parse_list(args).map_err(io::Error::other)?;
+    let links = store
+        .list(args.owner.as_deref(), args.limit)
+        .await
+        .map_err(failed)?;
+    for link in &links {
+        println!("{}", list_line(link));
+    }
+    Ok(())
+}
+
+/// Bring an existing database up to date. The indexes are created by
+/// opening the stores; links older than `created_at` get it from their `_id`.
+/// Running it again is harmless.
+pub async fn migrate(store: store::Url) -> io::Result<()> {
+    let backfilled = store.backfill_created_at().await.map_err(failed)?;
+    println!("indexes are in place, backfilled created_at of {backfilled} links");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::DateTime;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn create_arguments() {
+        assert_eq!(
+            parse_create(&args(&["https://example.com"])),
+            Ok(CreateArgs {
+                url: String::from("https://example.com"),
+                name: None,
+                owner: String::from(OWNER),
+            })
+        );
+        let parsed = parse_create(&args(&[
+            "--name",
+            "docs",
+            "https://example.com",
+            "--owner",
+            "ops",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("docs"));
+        assert_eq!(parsed.owner, "ops");
+        assert!(parse_create(&args(&[])).is_err());
+        assert!(parse_create(&args(&["https://a.com", "https://b.com"])).is_err());
+    }
+
+    #[test]
+    fn key_arguments() {
+        assert_eq!(parse_key("get", &args(&["abc"])), Ok("abc"));
+        assert!(parse_key("get", &args(&[])).is_err());
+        assert!(parse_key("delete", &args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn list_arguments() {
+        assert_eq!(
+            parse_list(&args(&[])),
+            Ok(ListArgs {
+                owner: None,
+                limit: LIST_LIMIT,
+            })
+        );
+        let parsed = parse_list(&args(&["--owner", "cli", "--limit", "5"])).unwrap();
+        assert_eq!(parsed.owner.as_deref(), Some("cli"));
+        assert_eq!(parsed.limit, 5);
+        assert!(parse_list(&args(&["--limit", "0"])).is_err());
+    }
+
+    #[test]
+    fn short_url_needs_public_url() {
+        assert_eq!(
+            Base::new(Some("https://sho.rt/"), "").short_url("abc"),
+            "https://sho.rt/api/abc"
+        );
+        assert_eq!(Base::new(None, "/links").short_url("abc"), "abc");
+    }
+
+    #[test]
+    fn short_url_follows_the_mount_path() {
+        assert_eq!(
+            Base::new(Some("https://example.com"), "/links/").short_url("abc"),
+            "https://example.com/links/api/abc"
+        );
+    }
+
+    #[test]
+    fn list_line_is_tab_separated() {
+        let link = model::Url::new("https://example.com", "abc");
+        assert_eq!(list_line(&link), "abc\t-\thttps://example.com");
+        let link = link.with_created_at(DateTime::from_millis(0));
+        assert_eq!(
+            list_line(&link),
+            "abc\t1970-01-01T00:00:00Z\thttps://example.com"
+        );
+    }
+}
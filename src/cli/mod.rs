@@ -0,0 +1,162 @@
+// Command-line interface of the fesghel binary.
+// Without a command, or with `serve`, it runs the HTTP server. The other
+// commands work on the configured database directly, so operators can
+// manage links without going through the API.
+
+mod link;
+mod transfer;
+
+use std::io;
+use std::slice;
+
+use crate::database;
+use crate::setting::Settings;
+use crate::store;
+
+pub const USAGE: &str = "\
+usage: fesghel [command]
+
+commands:
+  serve      run the HTTP server (default)
+  create     <url> [--name <key>] [--owner <name>]
+  get        <key>
+  delete     <key>
+  list       [--owner <name>] [--limit <n>]
+  import     <file> [--format csv|jsonl] [--source <shortener>] [--skip <lines>]
+             [--owner <name>] [--dry-run]
+  export     [--format jsonl|csv] [--clicks] [--output <file>]
+  migrate    create indexes and backfill created_at of older links
+  help       show this message";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Serve,
+    Create,
+    Get,
+    Delete,
+    List,
+    Import,
+    Export,
+    Migrate,
+    Help,
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "serve" => Ok(Command::Serve),
+            "create" => Ok(Command::Create),
+            "get" => Ok(Command::Get),
+            "delete" => Ok(Command::Delete),
+            "list" => Ok(Command::List),
+            "import" => Ok(Command::Import),
+            "export" => Ok(Command::Export),
+            "migrate" => Ok(Command::Migrate),
+            "help" | "--help" | "-h" => Ok(Command::Help),
+            _ => Err(format!("unknown command: {s}")),
+        }
+    }
+}
+
+/// Split the arguments into the command and the command's own arguments.
+pub fn parse(args: &[String]) -> Result<(Command, &[String]), String> {
+    let Some((name, rest)) = args.split_first() else {
+        return Ok((Command::Serve, args));
+    };
+    let command = name.parse()?;
+    if matches!(command, Command::Serve | Command::Migrate) && !rest.is_empty() {
+        return Err(format!("{name} takes no arguments"));
+    }
+    Ok((command, rest))
+}
+
+// The value of option `arg`, taken from the arguments that follow it.
+fn value(args: &mut slice::Iter<'_, String>, arg: &str) -> Result<String, String> {
+    args.next()
+        .cloned()
+        .ok_or_else(|| format!("{arg} needs a value"))
+}
+
+// Opening a store also creates its indexes.
+async fn urls(setting: &Settings) -> store::Url {
+    store::Url::new(database::connect(setting.database()).await).await
+}
+
+/// Run any command but `serve`, which is left to `main`.
+pub async fn run(command: Command, setting: &Settings, args: &[String]) -> io::Result<()> {
+    let server = setting.server();
+    match command {
+        Command::Serve => unreachable!("main starts the server itself"),
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Command::Create => {
+            let base = link::Base::new(server.public_url(), server.mount_path());
+            link::create(urls(setting).await, base, args).await
+        }
+        Command::Get => link::get(urls(setting).await, args).await,
+        Command::Delete => {
+            let db = database::connect(setting.database()).await;
+            let clicks = store::Click::new(db.clone()).await;
+            link::delete(store::Url::new(db).await, clicks, args).await
+        }
+        Command::List => link::list(urls(setting).await, args).await,
+        Command::Import => transfer::import(urls(setting).await, args).await,
+        Command::Export => {
+            let db = database::connect(setting.database()).await;
+            let clicks = store::Click::new(db.clone()).await;
+            transfer::export(store::Url::new(db).await, clicks, args).await
+        }
+        Command::Migrate => {
+            let db = database::connect(setting.database()).await;
+            // Only opened for its indexes.
+            let _ = store::Click::new(db.clone()).await;
+            link::migrate(store::Url::new(db).await).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn serve_is_the_default() {
+        assert_eq!(parse(&args(&[])), Ok((Command::Serve, &[][..])));
+        assert_eq!(parse(&args(&["serve"])), Ok((Command::Serve, &[][..])));
+    }
+
+    #[test]
+    fn command_keeps_its_arguments() {
+        let list = args(&["get", "abc"]);
+        assert_eq!(parse(&list), Ok((Command::Get, &list[1..])));
+        assert_eq!(parse(&args(&["--help"])).unwrap().0, Command::Help);
+    }
+
+    #[test]
+    fn option_values() {
+        let list = args(&["--owner", "ops", "--limit"]);
+        let mut iter = list.iter();
+        let arg = iter.next().unwrap();
+        assert_eq!(value(&mut iter, arg), Ok(String::from("ops")));
+        let arg = iter.next().unwrap();
+        assert_eq!(
+            value(&mut iter, arg),
+            Err(String::from("--limit needs a value"))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_stray_arguments() {
+        assert!(parse(&args(&["shorten"])).is_err());
+        assert!(parse(&args(&["serve", "--port", "80"])).is_err());
+        assert!(parse(&args(&["migrate", "now"])).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+// Bulk transfer commands.
+//   fesghel import <file> [--format csv|jsonl] [--source <shortener>] [--skip <lines>]
+//                  [--owner <name>] [--dry-run]
+//   fesghel export [--format jsonl|csv] [--clicks] [--output <file>]
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use futures_util::StreamExt;
+
+use super::value;
+use crate::export;
+use crate::import;
+use crate::store;
+
+#[derive(Debug, PartialEq)]
+struct ImportArgs {
+    path: String,
+    format: import::Format,
+    source: import::Source,
+    skip: usize,
+    owner: String,
+    dry_run: bool,
+}
+
+// Hand-rolled parsing is enough for one command with a handful of options.
+fn parse_import(args: &[String]) -> Result<ImportArgs, String> {
+    let mut path = None;
+    let mut format = None;
+    let mut source = import::Source::default();
+    let mut skip = 0;
+    let mut owner = String::from(import::DEFAULT_OWNER);
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // Options with a value take it from the following argument.
+        match arg.as_str() {
+            "--format" => format = Some(value(&mut args, arg)?.parse()?),
+            "--source" => source = value(&mut args, arg)?.parse()?,
+            "--dry-run" => dry_run = true,
+            "--skip" => {
+                skip = value(&mut args, arg)?
+                    .parse()
+                    .map_err(|_| "--skip needs a number")?
+            }
+            "--owner" => owner = value(&mut args, arg)?,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    let path = path.ok_or(
+        "usage: fesghel import <file> [--format csv|jsonl] [--source <shortener>] \
+         [--skip <lines>] [--owner <name>] [--dry-run]",
+    )?;
+    // Without `--format`, `.jsonl` files are JSON Lines and everything else CSV.
+    let format = format.unwrap_or(if path.ends_with(".jsonl") {
+        import::Format::Jsonl
+    } else {
+        import::Format::Csv
+    });
+    Ok(ImportArgs {
+        path,
+        format,
+        source,
+        skip,
+        owner,
+        dry_run,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+struct ExportArgs {
+    format: import::Format,
+    data: export::Data,
+    output: Option<String>,
+}
+
+fn parse_export(args: &[String]) -> Result<ExportArgs, String> {
+    let mut parsed = ExportArgs {
+        format: import::Format::Jsonl,
+        data: export::Data::Links,
+        output: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => parsed.format = value(&mut args, arg)?.parse()?,
+            "--output" => parsed.output = Some(value(&mut args, arg)?),
+            "--clicks" => parsed.data = export::Data::Clicks,
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Import links from a file and print the report as JSON.
+pub async fn import(store: store::Url, args: &[String]) -> io::Result<()> {
+    let args = parse_import(args).map_err(io::Error::other)?;
+    let reader = BufReader::new(File::open(&args.path)?);
+
+    let mut importer = import::Importer::new(store, args.format, &args.owner, args.skip)
+        .with_source(args.source)
+        .with_dry_run(args.dry_run);
+    // `split(b'\n')` tolerates invalid UTF-8, unlike `lines()`.
+    for line in reader.split(b'\n') {
+        importer
+            .line(&String::from_utf8_lossy(&line?))
+            .await
+            .map_err(|err| io::Error::other(err.to_string()))?;
+    }
+    let report = importer
+        .finish()
+        .await
+        .map_err(|err| io::Error::other(err.to_string()))?;
+
+    log::info!("import finished after line {}", report.lines());
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).map_err(io::Error::other)?
+    );
+    Ok(())
+}
+
+/// Write every link, or every click event with `--clicks`, to a file or stdout.
+pub async fn export(urls: store::Url, clicks: store::Click, args: &[String]) -> io::Result<()> {
+    let args = parse_export(args).map_err(io::Error::other)?;
+    let cursor_error = |err: store::Error| io::Error::other(err.to_string());
+    // Both sinks become one `Box<dyn Write>`, so the loop below needs no generics.
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let mut lines = match args.data {
+        export::Data::Links => {
+            export::lines(args.format, urls.all().await.map_err(cursor_error)?).boxed_local()
+        }
+        export::Data::Clicks => {
+            export::lines(args.format, clicks.all().await.map_err(cursor_error)?).boxed_local()
+        }
+    };
+    while let Some(line) = lines.next().await {
+        out.write_all(&line.map_err(io::Error::other)?)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn import_defaults() {
+        assert_eq!(
+            parse_import(&args(&["links.csv"])),
+            Ok(ImportArgs {
+                path: String::from("links.csv"),
+                format: import::Format::Csv,
+                source: import::Source::Fesghel,
+                skip: 0,
+                owner: String::from(import::DEFAULT_OWNER),
+                dry_run: false,
+            })
+        );
+        assert_eq!(
+            parse_import(&args(&["links.jsonl"])).unwrap().format,
+            import::Format::Jsonl
+        );
+    }
+
+    #[test]
+    fn import_options() {
+        let parsed = parse_import(&args(&[
+            "--format", "jsonl", "dump.txt", "--skip", "5000", "--owner", "team",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.format, import::Format::Jsonl);
+        assert_eq!(parsed.skip, 5000);
+        assert_eq!(parsed.owner, "team");
+
+        let parsed = parse_import(&args(&["links.csv", "--source", "bitly", "--dry-run"])).unwrap();
+        assert_eq!(parsed.source, import::Source::Bitly);
+        assert!(parsed.dry_run);
+    }
+
+    #[test]
+    fn export_arguments() {
+        assert_eq!(
+            parse_export(&args(&[])),
+            Ok(ExportArgs {
+                format: import::Format::Jsonl,
+                data: export::Data::Links,
+                output: None,
+            })
+        );
+        let parsed = parse_export(&args(&["--clicks", "--format", "csv", "--output", "c.csv"]));
+        assert_eq!(
+            parsed,
+            Ok(ExportArgs {
+                format: import::Format::Csv,
+                data: export::Data::Clicks,
+                output: Some(String::from("c.csv")),
+            })
+        );
+        assert!(parse_export(&args(&["links.csv"])).is_err());
+    }
+
+    #[test]
+    fn import_rejects_bad_arguments() {
+        assert!(parse_import(&args(&[])).is_err());
+        assert!(parse_import(&args(&["a.csv", "--skip"])).is_err());
+        assert!(parse_import(&args(&["a.csv", "--skip", "many"])).is_err());
+        assert!(parse_import(&args(&["a.csv", "b.csv"])).is_err());
+        assert!(parse_import(&args(&["a.csv", "--format", "xml"])).is_err());
+        assert!(parse_import(&args(&["a.csv", "--source", "tinyurl"])).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use actix_web::HttpRequest;
+use actix_web::http::header;
+use ipnet::IpNet;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::setting;
+
+// Header carrying the caller's API key.
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Whether `req` carries `Authorization: Bearer <token>` for the admin token.
+/// Without a configured token nobody is an admin.
+pub fn is_admin(req: &HttpRequest, token: Option<&str>) -> bool {
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        return false;
+    };
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        // Constant-time comparison, so response timing does not leak the token.
+        .is_some_and(|given| bool::from(given.as_bytes().ct_eq(token.as_bytes())))
+}
+
+// Reverse proxies whose `X-Forwarded-For` header is trusted.
+// Without trusted proxies the header is ignored, since any client can set it.
+#[derive(Debug, Clone, Default)]
+pub struct Proxies {
+    trusted: Vec<IpNet>,
+}
+
+impl Proxies {
+    /// Parse proxy addresses or CIDR ranges such as `10.0.0.1` or `10.0.0.0/8`.
+    pub fn new(trusted: &[String]) -> Result<Self, ipnet::AddrParseError> {
+        let trusted = trusted
+            .iter()
+            .map(|entry| {
+                // A bare address is a single-host network.
+                entry
+                    .parse::<IpAddr>()
+                    .map(IpNet::from)
+                    .or_else(|_| entry.parse::<IpNet>())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Proxies { trusted })
+    }
+
+    fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.trusted.iter().any(|net| net.contains(ip))
+    }
+
+    /// Address of the client that sent `req`.
+    /// When the peer is a trusted proxy, `X-Forwarded-For` is walked from the
+    /// right and the first untrusted hop wins - entries further left were
+    /// supplied by the client and cannot be trusted.
+    pub fn ip(&self, req: &HttpRequest) -> Option<IpAddr> {
+        let peer = req.peer_addr()?.ip();
+        if !self.is_trusted(&peer) {
+            return Some(peer);
+        }
+
+        let hops: Vec<IpAddr> = req
+            .headers()
+            .get_all("X-Forwarded-For")
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|hop| hop.trim().parse().ok())
+            .collect();
+
+        // `rev()` walks right to left; when every hop is a trusted proxy,
+        // the leftmost one is the best guess left.
+        hops.iter()
+            .rev()
+            .find(|hop| !self.is_trusted(hop))
+            .or(hops.first())
+            .copied()
+            .or(Some(peer))
+    }
+
+    /// Identify the caller for rate limits, quotas and link ownership.
+    /// An issued API key wins; any other request, including one with an
+    /// unknown key, is identified by its client IP address, so clients cannot
+    /// pick a fresh identity by making up keys.
+    pub fn identity(&self, req: &HttpRequest, keys: &ApiKeys) -> String {
+        if let Some(identity) = keys.identity(req) {
+            return identity;
+        }
+
+        match self.ip(req) {
+            Some(ip) => format!("ip:{ip}"),
+            None => String::from("ip:unknown"),
+        }
+    }
+}
+
+// Issued API keys, by the SHA-256 digest of the key. Looking up digests
+// means the secrets themselves are never compared byte by byte.
+// `Arc` shares the map between the workers' handler states.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    names: Arc<HashMap<[u8; 32], String>>,
+}
+
+impl ApiKeys {
+    /// Keys from `(name, key)` pairs. Empty keys are ignored.
+    pub fn new<'a>(keys: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let names = keys
+            .into_iter()
+            .filter(|(_, key)| !key.is_empty())
+            .map(|(name, key)| (Sha256::digest(key.as_bytes()).into(), name.to_string()))
+            .collect();
+        ApiKeys {
+            names: Arc::new(names),
+        }
+    }
+
+    pub fn from_settings(api: &setting::Api) -> Self {
+        ApiKeys::new(api.keys().iter().map(|k| (k.name(), k.key())))
+    }
+
+    /// Name of the issued key `req` carries in the `X-API-Key` header, if any.
+    pub fn name(&self, req: &HttpRequest) -> Option<&str> {
+        // `and_then` chains fallible steps: missing header or non-UTF-8 value yields None.
+        let key = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())?;
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+        self.names.get(&digest).map(String::as_str)
+    }
+
+    /// Identity of a request with an issued key, the same one `Proxies::identity`
+    /// gives it. Unlike a client address it proves who is calling.
+    pub fn identity(&self, req: &HttpRequest) -> Option<String> {
+        self.name(req).map(|name| format!("key:{name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn proxies(trusted: &[&str]) -> Proxies {
+        let trusted: Vec<String> = trusted.iter().map(|s| s.to_string()).collect();
+        Proxies::new(&trusted).unwrap()
+    }
+
+    #[test]
+    fn api_key_takes_precedence() {
+        let req = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "secret"))
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_http_request();
+        let keys = ApiKeys::new([("team-a", "secret")]);
+        assert_eq!(Proxies::default().identity(&req, &keys), "key:team-a");
+    }
+
+    #[test]
+    fn unknown_api_key_is_ignored() {
+        let req = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "made-up"))
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_http_request();
+        let keys = ApiKeys::new([("team-a", "secret")]);
+        assert_eq!(Proxies::default().identity(&req, &keys), "ip:10.0.0.1");
+    }
+
+    #[test]
+    fn made_up_keys_share_one_identity() {
+        // Quotas and buckets are keyed on the identity, so rotating keys
+        // must not produce fresh ones.
+        let keys = ApiKeys::new([("team-a", "secret")]);
+        let identities: Vec<String> = ["one", "two", ""]
+            .into_iter()
+            .map(|key| {
+                let req = TestRequest::default()
+                    .insert_header((API_KEY_HEADER, key))
+                    .peer_addr("10.0.0.1:1234".parse().unwrap())
+                    .to_http_request();
+                Proxies::default().identity(&req, &keys)
+            })
+            .collect();
+        assert_eq!(identities, ["ip:10.0.0.1"; 3]);
+    }
+
+    #[test]
+    fn falls_back_to_peer_ip() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_http_request();
+        assert_eq!(
+            Proxies::default().identity(&req, &ApiKeys::default()),
+            "ip:10.0.0.1"
+        );
+    }
+
+    #[test]
+    fn forwarded_for_ignored_from_untrusted_peer() {
+        let req = TestRequest::default()
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .peer_addr("203.0.113.9:1234".parse().unwrap())
+            .to_http_request();
+        let ip = proxies(&["10.0.0.0/8"]).ip(&req).unwrap();
+        assert_eq!(ip.to_string(), "203.0.113.9");
+    }
+
+    #[test]
+    fn forwarded_for_walked_from_the_right() {
+        // The client spoofed 6.6.6.6; the proxy chain appended the real address.
+        let req = TestRequest::default()
+            .insert_header(("X-Forwarded-For", "6.6.6.6, 198.51.100.7, 10.0.0.2"))
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_http_request();
+        let ip = proxies(&["10.0.0.0/8"]).ip(&req).unwrap();
+        assert_eq!(ip.to_string(), "198.51.100.7");
+    }
+
+    #[test]
+    fn bare_addresses_are_accepted() {
+        let req = TestRequest::default()
+            .insert_header(("X-Forwarded-For", "198.51.100.7"))
+            .peer_addr("192.0.2.1:1234".parse().unwrap())
+            .to_http_request();
+        let ip = proxies(&["192.0.2.1"]).ip(&req).unwrap();
+        assert_eq!(ip.to_string(), "198.51.100.7");
+    }
+
+    #[test]
+    fn admin_needs_matching_token() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+            .to_http_request();
+        assert!(is_admin(&req, Some("s3cret")));
+        assert!(!is_admin(&req, Some("other")));
+        assert!(!is_admin(&req, None));
+        assert!(!is_admin(
+            &TestRequest::default().to_http_request(),
+            Some("s3cret")
+        ));
+    }
+
+    #[test]
+    fn empty_admin_token_disables_admin() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer "))
+            .to_http_request();
+        assert!(!is_admin(&req, Some("")));
+    }
+
+    #[test]
+    fn invalid_proxy_is_rejected() {
+        assert!(Proxies::new(&["not-an-ip".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// Helpers for building the final redirect target from a stored destination.
+
+use url::Url as ParsedUrl;
+use url::form_urlencoded;
+
+/// Append `suffix` to the destination path and merge `query` into its query string.
+/// Parameters from `query` replace destination parameters with the same name.
+pub fn build(base: &str, suffix: Option<&str>, query: Option<&str>) -> String {
+    // Stored destinations are validated on creation, but older documents may
+    // predate validation - hand them back untouched instead of failing.
+    let Ok(mut url) = ParsedUrl::parse(base) else {
+        return base.to_string();
+    };
+
+    if let Some(suffix) = suffix.filter(|s| !s.is_empty()) {
+        let path = format!(
+            "{}/{}",
+            url.path().trim_end_matches('/'),
+            suffix.trim_start_matches('/')
+        );
+        url.set_path(&path);
+    }
+
+    if let Some(query) = query.filter(|q| !q.is_empty()) {
+        // `into_owned()` turns borrowed `Cow` pairs into owned Strings so they
+        // outlive the borrow of `url` taken by `query_pairs()`.
+        let incoming: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let mut pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .into_owned()
+            .filter(|(k, _)| !incoming.iter().any(|(ik, _)| ik == k))
+            .collect();
+        pairs.extend(incoming);
+
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_without_suffix_or_query() {
+        assert_eq!(
+            build("https://example.com/a?x=1", None, None),
+            "https://example.com/a?x=1"
+        );
+    }
+
+    #[test]
+    fn appends_path_suffix() {
+        assert_eq!(
+            build("https://docs.example.com", Some("v2/intro"), None),
+            "https://docs.example.com/v2/intro"
+        );
+        assert_eq!(
+            build("https://example.com/docs/", Some("v2"), None),
+            "https://example.com/docs/v2"
+        );
+    }
+
+    #[test]
+    fn merges_query_string() {
+        assert_eq!(
+            build("https://example.com/?a=1", None, Some("utm_source=x")),
+            "https://example.com/?a=1&utm_source=x"
+        );
+    }
+
+    #[test]
+    fn incoming_parameters_override() {
+        assert_eq!(
+            build(
+                "https://example.com/?utm_source=a&b=2",
+                None,
+                Some("utm_source=x")
+            ),
+            "https://example.com/?b=2&utm_source=x"
+        );
+    }
+
+    #[test]
+    fn suffix_and_query_together() {
+        assert_eq!(
+            build("https://example.com/base", Some("page"), Some("q=1")),
+            "https://example.com/base/page?q=1"
+        );
+    }
+
+    #[test]
+    fn invalid_base_is_returned_as_is() {
+        assert_eq!(build("not a url", Some("x"), Some("y=1")), "not a url");
+    }
+}
@@ -0,0 +1,275 @@
+// Streaming export of links and click events for backups and BI.
+// Records are encoded one at a time straight off a MongoDB cursor, so an
+// export never holds more than a cursor batch in memory.
+//
+// Both formats use the importer's schema, so an export can be imported
+// again: timestamps are RFC 3339 strings. JSON Lines carry every field of a
+// link, CSV the flat ones only. Password hashes are never exported; a
+// protected link is marked with `protected` set to `true` instead.
+
+use std::fmt;
+
+use futures_util::{Stream, StreamExt, stream};
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::import::Format;
+use crate::model;
+
+const LINK_COLUMNS: &[&str] = &[
+    "key",
+    "url",
+    "created_at",
+    "owner",
+    "custom",
+    "redirect",
+    "max_clicks",
+    "not_before",
+    "not_after",
+    "title",
+    "description",
+    "image",
+    "protected",
+];
+const CLICK_COLUMNS: &[&str] = &["key", "at", "variant", "country"];
+
+// Which collection to export.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Data {
+    #[default]
+    Links,
+    Clicks,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Database(mongodb::error::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Database(err) => write!(f, "reading from database failed: {}", err),
+            Error::Json(err) => write!(f, "encoding document failed: {}", err),
+            Error::Csv(err) => write!(f, "writing CSV failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Something that can be written as one export record.
+pub trait Record {
+    const COLUMNS: &'static [&'static str];
+
+    // Cells in `COLUMNS` order; missing values are empty.
+    fn cells(&self) -> Vec<String>;
+
+    // Every field as one JSON object, in the importer's schema.
+    fn fields(&self) -> Result<Map<String, Value>, Error>;
+}
+
+// A record's own serialization, with its timestamps replaced by RFC 3339
+// strings. `bson::DateTime` would otherwise come out as `{"$date": ..}`.
+fn document(
+    record: &impl Serialize,
+    timestamps: &[(&str, Option<DateTime>)],
+) -> Result<Map<String, Value>, Error> {
+    let Value::Object(mut fields) = serde_json::to_value(record).map_err(Error::Json)? else {
+        unreachable!("records serialize as objects");
+    };
+    for (name, at) in timestamps {
+        match at.and_then(|at| at.try_to_rfc3339_string().ok()) {
+            Some(at) => fields.insert(name.to_string(), Value::String(at)),
+            None => fields.remove(*name),
+        };
+    }
+    Ok(fields)
+}
+
+// CSV has no date type; RFC 3339 is what the importer reads.
+fn timestamp(at: Option<DateTime>) -> String {
+    at.and_then(|at| at.try_to_rfc3339_string().ok())
+        .unwrap_or_default()
+}
+
+// `Option<impl ToString>` turns absent values into empty cells.
+fn cell(value: Option<impl ToString>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl Record for model::Url {
+    const COLUMNS: &'static [&'static str] = LINK_COLUMNS;
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.key().to_string(),
+            self.url().to_string(),
+            timestamp(self.created_at()),
+            cell(self.owner()),
+            self.custom().to_string(),
+            cell(self.redirect().map(|r| r.status())),
+            cell(self.max_clicks()),
+            timestamp(self.not_before()),
+            timestamp(self.not_after()),
+            cell(self.title()),
+            cell(self.description()),
+            cell(self.image()),
+            cell(self.password().map(|_| true)),
+        ]
+    }
+
+    fn fields(&self) -> Result<Map<String, Value>, Error> {
+        let mut fields = document(
+            self,
+            &[
+                ("created_at", self.created_at()),
+                ("not_before", self.not_before()),
+                ("not_after", self.not_after()),
+            ],
+        )?;
+        if fields.remove("password").is_some() {
+            fields.insert(String::from("protected"), Value::Bool(true));
+        }
+        Ok(fields)
+    }
+}
+
+impl Record for model::Click {
+    const COLUMNS: &'static [&'static str] = CLICK_COLUMNS;
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.key().to_string(),
+            timestamp(Some(self.at())),
+            cell(self.variant()),
+            cell(self.country()),
+        ]
+    }
+
+    fn fields(&self) -> Result<Map<String, Value>, Error> {
+        document(self, &[("at", Some(self.at()))])
+    }
+}
+
+fn csv_line<I, S>(cells: I) -> Result<Vec<u8>, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(cells).map_err(Error::Csv)?;
+    writer
+        .into_inner()
+        .map_err(|err| Error::Csv(err.into_error().into()))
+}
+
+/// One record as a line of output, including the trailing newline.
+pub fn line<R: Record>(format: Format, record: &R) -> Result<Vec<u8>, Error> {
+    match format {
+        Format::Csv => csv_line(record.cells()),
+        Format::Jsonl => {
+            let mut line = serde_json::to_vec(&record.fields()?).map_err(Error::Json)?;
+            line.push(b'\n');
+            Ok(line)
+        }
+    }
+}
+
+/// Encode every record of `records` as it arrives. CSV output starts with
+/// a header row.
+pub fn lines<R, S>(format: Format, records: S) -> impl Stream<Item = Result<Vec<u8>, Error>>
+where
+    R: Record,
+    S: Stream<Item = Result<R, mongodb::error::Error>>,
+{
+    let header = match format {
+        Format::Csv => Some(csv_line(R::COLUMNS)),
+        Format::Jsonl => None,
+    };
+    // `stream::iter(Option)` yields the header once, or nothing.
+    stream::iter(header).chain(records.map(move |record| {
+        record
+            .map_err(Error::Database)
+            .and_then(|record| line(format, &record))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link() -> model::Url {
+        model::Url::new("https://example.com/?a=1,2", "abc")
+            .with_owner("key:team")
+            .with_created_at(DateTime::parse_rfc3339_str("2024-01-02T03:04:05Z").unwrap())
+            .with_open_graph(Some("Launch, \"day\""), None, None)
+    }
+
+    #[test]
+    fn link_as_csv() {
+        let line = String::from_utf8(line(Format::Csv, &link()).unwrap()).unwrap();
+        assert_eq!(
+            line,
+            "abc,\"https://example.com/?a=1,2\",2024-01-02T03:04:05Z,key:team,false,,,,,\"Launch, \"\"day\"\"\",,,\n"
+        );
+    }
+
+    #[test]
+    fn link_as_jsonl() {
+        let line = String::from_utf8(line(Format::Jsonl, &link()).unwrap()).unwrap();
+        assert!(line.ends_with('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["key"], "abc");
+        assert_eq!(value["created_at"], "2024-01-02T03:04:05Z");
+        assert_eq!(value["owner"], "key:team");
+        assert_eq!(value["custom"], false);
+        assert!(value.get("not_after").is_none());
+    }
+
+    #[test]
+    fn jsonl_never_contains_password_hashes() {
+        let link = link().with_password(Some("s3cret"));
+        let line = String::from_utf8(line(Format::Jsonl, &link).unwrap()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(value.get("password").is_none());
+        assert_eq!(value["protected"], true);
+    }
+
+    #[test]
+    fn click_as_jsonl() {
+        let click = model::Click::new("abc", None);
+        let line = String::from_utf8(line(Format::Jsonl, &click).unwrap()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(value["at"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn click_as_csv() {
+        let click = model::Click::new("abc", Some(1)).with_country(Some("DE".to_string()));
+        let line = String::from_utf8(line(Format::Csv, &click).unwrap()).unwrap();
+        assert!(line.starts_with("abc,"));
+        assert!(line.ends_with(",1,DE\n"));
+    }
+
+    #[actix_web::test]
+    async fn csv_stream_starts_with_header() {
+        let records = stream::iter(vec![Ok(link())]);
+        let out: Vec<Vec<u8>> = lines(Format::Csv, records)
+            .map(|line| line.unwrap())
+            .collect()
+            .await;
+        assert_eq!(out.len(), 2);
+        assert!(out[0].starts_with(b"key,url,created_at,"));
+    }
+
+    #[actix_web::test]
+    async fn jsonl_stream_has_no_header() {
+        let records = stream::iter(vec![Ok(link()), Ok(link())]);
+        assert_eq!(lines(Format::Jsonl, records).count().await, 2);
+    }
+}
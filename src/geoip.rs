@@ -0,0 +1,48 @@
+// Country lookup backed by an offline MaxMind (GeoIP2/GeoLite2) database.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use maxminddb::{MaxMindDbError, Reader, geoip2};
+
+// The database is read into memory once and shared by all workers.
+// `None` means geo lookups are disabled.
+#[derive(Clone, Default)]
+pub struct GeoIp {
+    reader: Option<Arc<Reader<Vec<u8>>>>,
+}
+
+impl GeoIp {
+    /// Load the database at `path`, or disable lookups when no path is configured.
+    pub fn open(path: Option<&str>) -> Result<Self, MaxMindDbError> {
+        let reader = match path {
+            Some(path) => Some(Arc::new(Reader::open_readfile(path)?)),
+            None => None,
+        };
+        Ok(GeoIp { reader })
+    }
+
+    /// ISO 3166-1 alpha-2 code of the country `ip` is located in, if known.
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let reader = self.reader.as_ref()?;
+        // Lookup failures (private ranges, corrupt records) just mean "unknown".
+        let record: geoip2::Country = reader.lookup(ip).ok()?.decode().ok()??;
+        record.country.iso_code.map(str::to_uppercase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_lookup_knows_nothing() {
+        let geo = GeoIp::open(None).unwrap();
+        assert_eq!(geo.country("8.8.8.8".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn missing_database_is_an_error() {
+        assert!(GeoIp::open(Some("/nonexistent/GeoLite2-Country.mmdb")).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use actix_web::http::StatusCode;
+use actix_web::http::header;
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse, Responder, ResponseError, Scope, get, post, web};
+use futures_util::StreamExt;
+
+use crate::client;
+use crate::export;
+use crate::import;
+use crate::metrics;
+use crate::request;
+use crate::response;
+use crate::store;
+
+// State of the admin endpoints, separate from the public API's.
+pub struct State {
+    store: store::Url,
+    clicks: store::Click,
+    token: Option<String>,
+}
+
+impl State {
+    pub fn new(store: store::Url, clicks: store::Click, token: Option<&str>) -> Self {
+        State {
+            store,
+            clicks,
+            token: token.map(String::from),
+        }
+    }
+}
+
+// Admin endpoints answer 401 to everyone without the configured token.
+fn unauthorized(req: &HttpRequest, data: &State) -> Option<HttpResponse> {
+    if client::is_admin(req, data.token.as_deref()) {
+        return None;
+    }
+    metrics::inc_error("unauthorized");
+    let mut resp = response::Problem::new(StatusCode::UNAUTHORIZED, "unauthorized")
+        .with_detail("admin token required")
+        .error_response();
+    resp.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        header::HeaderValue::from_static("Bearer"),
+    );
+    Some(resp)
+}
+
+fn internal_error(err: store::Error) -> HttpResponse {
+    log::error!("{err}");
+    metrics::inc_error("database");
+    err.error_response()
+}
+
+// Bulk import from a CSV or JSON Lines body.
+// `web::Payload` is the raw body stream: it is split into lines as chunks
+// arrive, so the upload is never held in memory as a whole.
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "admin",
+    params(
+        ("format" = Option<String>, Query, description = "`csv` (default) or `jsonl`"),
+        ("skip" = Option<usize>, Query, description = "Lines already handled by an earlier run"),
+        ("owner" = Option<String>, Query, description = "Owner of the imported links"),
+        (
+            "source" = Option<String>,
+            Query,
+            description = "`fesghel` (default), `yourls`, `kutt`, `shlink` or `bitly`"
+        ),
+        ("dry_run" = Option<bool>, Query, description = "Validate and report without writing"),
+    ),
+    request_body(
+        content((String = "text/csv"), (String = "application/x-ndjson")),
+        description = "Links, one per line"
+    ),
+    responses(
+        (status = 200, description = "What was imported", body = import::Report),
+        (
+            status = 401,
+            description = "Admin token required",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    ),
+    security(("admin" = []))
+)]
+#[post("/import")]
+async fn import_links(
+    req: HttpRequest,
+    data: web::Data<State>,
+    options: web::Query<request::Import>,
+    mut body: web::Payload,
+) -> impl Responder {
+    if let Some(resp) = unauthorized(&req, &data) {
+        return resp;
+    }
+
+    let mut importer = import::Importer::new(
+        data.store.clone(),
+        options.format(),
+        options.owner(),
+        options.skip(),
+    )
+    .with_source(options.source())
+    .with_dry_run(options.dry_run());
+    let mut buffer: Vec<u8> = Vec::new();
+
+    // `StreamExt::next` yields body chunks until the upload ends.
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                return response::Problem::new(StatusCode::BAD_REQUEST, "invalid_body")
+                    .with_detail(err)
+                    .error_response();
+            }
+        };
+        buffer.extend_from_slice(&chunk);
+        // `drain(..=end)` removes the line including its newline from the buffer.
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if let Err(err) = importer.line(&String::from_utf8_lossy(&line)).await {
+                return internal_error(err);
+            }
+        }
+    }
+    // The last line may lack a trailing newline.
+    if !buffer.is_empty()
+        && let Err(err) = importer.line(&String::from_utf8_lossy(&buffer)).await
+    {
+        return internal_error(err);
+    }
+
+    match importer.finish().await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => internal_error(err),
+    }
+}
+
+// Stream every link or click event. `streaming` sends the body with chunked
+// transfer encoding as records come off the cursor. An error midway can only
+// cut the response short, so it is logged.
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "admin",
+    params(
+        ("data" = Option<String>, Query, description = "`links` (default) or `clicks`"),
+        ("format" = Option<String>, Query, description = "`jsonl` (default) or `csv`"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Every record, one per line",
+            content((String = "application/x-ndjson"), (String = "text/csv"))
+        ),
+        (
+            status = 401,
+            description = "Admin token required",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    ),
+    security(("admin" = []))
+)]
+#[get("/export")]
+async fn export_records(
+    req: HttpRequest,
+    data: web::Data<State>,
+    options: web::Query<request::Export>,
+) -> impl Responder {
+    if let Some(resp) = unauthorized(&req, &data) {
+        return resp;
+    }
+
+    let format = options.format();
+    // `boxed_local` erases the two stream types so both arms match.
+    let lines = match options.data() {
+        export::Data::Links => match data.store.all().await {
+            Ok(cursor) => export::lines(format, cursor).boxed_local(),
+            Err(err) => return internal_error(err),
+        },
+        export::Data::Clicks => match data.clicks.all().await {
+            Ok(cursor) => export::lines(format, cursor).boxed_local(),
+            Err(err) => return internal_error(err),
+        },
+    };
+    let body = lines.map(|line| {
+        line.map(Bytes::from).inspect_err(|err| {
+            log::error!("export aborted: {err}");
+        })
+    });
+
+    let content_type = match format {
+        import::Format::Csv => "text/csv; charset=utf-8",
+        import::Format::Jsonl => "application/x-ndjson",
+    };
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .streaming(body)
+}
+
+pub fn register(state: State, scope: Scope) -> Scope {
+    scope
+        .app_data(web::Data::new(state))
+        .service(import_links)
+        .service(export_records)
+}
+
+// Routes of this module for the OpenAPI document, relative to `/api`.
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(import_links, export_records))]
+pub struct Api;
@@ -0,0 +1,218 @@
+// OpenAPI document of the whole API and the interactive docs page.
+// The routes and their request and response types are described next to the
+// handlers with `#[utoipa::path]` and `#[derive(ToSchema)]`; this module only
+// puts the pieces together, so the document cannot drift from the code.
+
+use std::io;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use actix_web::http::header::ContentType;
+use actix_web::{HttpRequest, HttpResponse, Responder, Scope, get, web};
+use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::server::Server;
+
+use super::{admin, healthz, url};
+use crate::client;
+use crate::page;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "fesghel",
+        description = "URL shortener with click analytics",
+        license(name = "GPL-3.0")
+    ),
+    nest(
+        (path = "/api", api = url::Api),
+        (path = "/api", api = admin::Api),
+        (path = "/healthz", api = healthz::Api),
+    ),
+    modifiers(&Security),
+    // Links belong to the caller's API key, or to their IP address without one.
+    security((), ("api_key" = [])),
+    tags(
+        (name = "links", description = "Create and manage the caller's links"),
+        (name = "redirects", description = "Follow short links"),
+        (name = "admin", description = "Bulk import and export, with the admin token"),
+        (name = "health", description = "Liveness check"),
+    )
+)]
+pub struct ApiDoc;
+
+// Security schemes cannot be declared in the attribute, so they are added
+// by a `Modify` implementation once the document is built.
+struct Security;
+
+impl utoipa::Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(client::API_KEY_HEADER))),
+        );
+        components.add_security_scheme(
+            "admin",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+// The document never changes while the server runs, so it is built once.
+static SPEC: LazyLock<utoipa::openapi::OpenApi> = LazyLock::new(ApiDoc::openapi);
+
+// Registered in the `/api` scope, before `/{name}` would match it.
+// Paths in the document start at the service's root, so its server is the
+// prefix a host application mounted the service under; "Try it out" then
+// sends requests to the right place.
+#[get("/openapi.json")]
+async fn spec(req: HttpRequest) -> impl Responder {
+    let mount = req
+        .path()
+        .strip_suffix("/openapi.json")
+        .and_then(|scope| scope.strip_suffix(crate::service::API_SCOPE))
+        .filter(|mount| !mount.is_empty())
+        .unwrap_or("/");
+
+    let mut doc = SPEC.clone();
+    doc.servers = Some(vec![Server::new(mount)]);
+    match doc.to_pretty_json() {
+        Ok(json) => HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .body(json),
+        Err(err) => {
+            log::error!("{err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Swagger UI files from a local copy of the `swagger-ui-dist` package,
+/// served next to the docs page so it needs no third-party CDN.
+pub struct Assets {
+    css: Vec<u8>,
+    js: Vec<u8>,
+}
+
+impl Assets {
+    /// Read `swagger-ui.css` and `swagger-ui-bundle.js` from `dir` once, at startup.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        Ok(Assets {
+            css: std::fs::read(dir.join("swagger-ui.css"))?,
+            js: std::fs::read(dir.join("swagger-ui-bundle.js"))?,
+        })
+    }
+}
+
+#[get("/swagger-ui.css")]
+async fn swagger_css(assets: web::Data<Assets>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/css")
+        .body(assets.css.clone())
+}
+
+#[get("/swagger-ui-bundle.js")]
+async fn swagger_js(assets: web::Data<Assets>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/javascript")
+        .body(assets.js.clone())
+}
+
+// Relative to the scope it is registered in, usually `/docs`.
+#[get("")]
+async fn docs(assets: Option<web::Data<Assets>>) -> impl Responder {
+    // Both URLs are relative to `/docs`: the document is in the sibling
+    // `/api` scope, local assets are under `/docs` itself.
+    let assets = assets.map(|_| "docs");
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(page::api_docs("api/openapi.json", assets))
+}
+
+/// The OpenAPI document at `/openapi.json` of `scope`.
+pub fn register(scope: Scope) -> Scope {
+    scope.service(spec)
+}
+
+/// The Swagger UI page, at the root of `scope`, with the files of `assets`
+/// when given.
+pub fn register_page(scope: Scope, assets: Option<web::Data<Assets>>) -> Scope {
+    match assets {
+        Some(assets) => scope
+            .app_data(assets)
+            .service(swagger_css)
+            .service(swagger_js)
+            .service(docs),
+        None => scope.service(docs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_every_api() {
+        let doc = ApiDoc::openapi();
+        for path in [
+            "/api/urls",
+            "/api/urls/{name}",
+            "/api/urls/{name}/stats",
+            "/api/{name}",
+            "/api/import",
+            "/api/export",
+            "/healthz",
+        ] {
+            assert!(doc.paths.paths.contains_key(path), "missing {path}");
+        }
+        let schemas = doc.components.unwrap().schemas;
+        for schema in ["Url", "Details", "Stats", "Report", "Rule"] {
+            assert!(schemas.contains_key(schema), "missing {schema}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn document_names_the_mount_as_server() {
+        use actix_web::{App, test};
+
+        let app = test::init_service(
+            App::new().service(web::scope("/links").service(register(web::scope("/api")))),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/links/api/openapi.json")
+            .to_request();
+        let doc: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(doc["servers"][0]["url"], "/links");
+    }
+
+    #[actix_web::test]
+    async fn page_serves_local_assets() {
+        use actix_web::{App, test};
+
+        let dir = std::env::temp_dir().join(format!("fesghel-docs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("swagger-ui.css"), "body {}").unwrap();
+        std::fs::write(dir.join("swagger-ui-bundle.js"), "var x;").unwrap();
+        let assets = web::Data::new(Assets::load(&dir).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let app = test::init_service(
+            App::new().service(register_page(web::scope("/docs"), Some(assets))),
+        )
+        .await;
+        let page =
+            test::call_and_read_body(&app, test::TestRequest::get().uri("/docs").to_request())
+                .await;
+        assert!(
+            std::str::from_utf8(&page)
+                .unwrap()
+                .contains(r#"src="docs/swagger-ui-bundle.js""#)
+        );
+        let js = test::TestRequest::get()
+            .uri("/docs/swagger-ui-bundle.js")
+            .to_request();
+        assert_eq!(test::call_and_read_body(&app, js).await, "var x;");
+    }
+}
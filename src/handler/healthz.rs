@@ -1,6 +1,13 @@
 use actix_web::{HttpResponse, Responder, Scope, get};
 
-#[get("/healthz")]
+// Relative to the scope it is registered in, usually `/healthz`.
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "health",
+    responses((status = 204, description = "The server is up"))
+)]
+#[get("")]
 async fn healthz() -> impl Responder {
     HttpResponse::NoContent()
 }
@@ -8,3 +15,7 @@ async fn healthz() -> impl Responder {
 pub fn register(scope: Scope) -> Scope {
     scope.service(healthz)
 }
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(healthz))]
+pub struct Api;
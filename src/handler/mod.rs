@@ -1,2 +1,4 @@
+pub mod admin;
+pub mod docs;
 pub mod healthz;
 pub mod url;
@@ -1,16 +1,52 @@
-use actix_web::http::header;
-use actix_web::{HttpResponse, Responder, Scope, get, post, web};
+use actix_web::HttpMessage;
+use actix_web::cookie::Cookie;
+use actix_web::cookie::time::Duration as CookieDuration;
+use actix_web::http::header::ContentType;
+use actix_web::http::{StatusCode, header};
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, ResponseError, Scope, delete, get, post, web,
+};
+
+use mongodb::bson::DateTime;
 
 // `crate::` refers to the root of the current crate (project).
+use crate::client;
+use crate::destination;
+use crate::geoip;
 use crate::metrics;
 use crate::model;
+use crate::page;
+use crate::qr;
+use crate::ratelimit;
 use crate::request;
+use crate::response;
+use crate::setting;
 use crate::store;
+use crate::useragent;
+
+// How long a sticky split link keeps a visitor on the same target.
+const STICKY_DAYS: i64 = 30;
+// QR codes only encode the short URL, so clients may keep them for a day.
+const QR_MAX_AGE: u64 = 86400;
+// Most links accepted by one batch create.
+const MAX_BATCH: usize = 1000;
 
 // A struct holding application state. In Rust, structs are the primary way
 // to create custom types that group related data together.
 pub struct State {
     store: store::Url,
+    clicks: store::Click,
+    create_limit: ratelimit::Limiter,
+    redirect_limit: ratelimit::Limiter,
+    password_limit: ratelimit::Limiter,
+    quota: setting::Quota,
+    redirect: model::Redirect,
+    max_age: u64,
+    proxies: client::Proxies,
+    api_keys: client::ApiKeys,
+    geoip: geoip::GeoIp,
+    public_url: Option<String>,
+    preview: bool,
 }
 
 // `impl` block defines methods associated with a type.
@@ -18,46 +54,290 @@ pub struct State {
 impl State {
     // Associated function (no self) - called via `State::new()`.
     // `Self` is an alias for the implementing type (`State`).
-    pub fn new(store: store::Url) -> Self {
+    pub fn new(store: store::Url, clicks: store::Click) -> Self {
         // Field init shorthand: `store: store` can be written as just `store`.
-        State { store }
+        State {
+            store,
+            clicks,
+            create_limit: ratelimit::Limiter::unlimited(),
+            redirect_limit: ratelimit::Limiter::unlimited(),
+            password_limit: ratelimit::Limiter::unlimited(),
+            quota: setting::Quota::default(),
+            redirect: model::Redirect::Temporary,
+            max_age: 0,
+            proxies: client::Proxies::default(),
+            api_keys: client::ApiKeys::default(),
+            geoip: geoip::GeoIp::default(),
+            public_url: None,
+            preview: false,
+        }
+    }
+
+    // Builder-style setter: takes `self` by value and returns it for chaining.
+    pub fn with_rate_limits(
+        mut self,
+        create_limit: ratelimit::Limiter,
+        redirect_limit: ratelimit::Limiter,
+    ) -> Self {
+        self.create_limit = create_limit;
+        self.redirect_limit = redirect_limit;
+        self
+    }
+
+    // Failed password attempts are counted per link key, not per client,
+    // so guessing from many addresses does not help.
+    pub fn with_password_limit(mut self, password_limit: ratelimit::Limiter) -> Self {
+        self.password_limit = password_limit;
+        self
+    }
+
+    pub fn with_quota(mut self, quota: setting::Quota) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    // Server-wide redirect status and cache lifetime for permanent redirects.
+    pub fn with_redirect(mut self, redirect: model::Redirect, max_age: u64) -> Self {
+        self.redirect = redirect;
+        self.max_age = max_age;
+        self
+    }
+
+    // Trusted reverse proxies, used to find the real client address.
+    pub fn with_proxies(mut self, proxies: client::Proxies) -> Self {
+        self.proxies = proxies;
+        self
+    }
+
+    // Issued API keys; callers without one are identified by address.
+    pub fn with_api_keys(mut self, api_keys: client::ApiKeys) -> Self {
+        self.api_keys = api_keys;
+        self
+    }
+
+    // Serve the preview page for every link, not only those asking for it.
+    pub fn with_preview(mut self, preview: bool) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    pub fn with_geoip(mut self, geoip: geoip::GeoIp) -> Self {
+        self.geoip = geoip;
+        self
+    }
+
+    // Base URL for links handed out to clients; a trailing slash is dropped
+    // so paths can be appended directly.
+    pub fn with_public_url(mut self, public_url: Option<&str>) -> Self {
+        self.public_url = public_url.map(|u| u.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Absolute short URL of `key`.
+    /// `url_for` resolves the path of the `fetch` route including its scope,
+    /// and the host as seen by the client unless a public URL is configured.
+    fn short_url(&self, req: &HttpRequest, key: &str) -> String {
+        // `fetch` always has a single `{name}` segment, so generation cannot fail.
+        let Ok(url) = req.url_for("fetch", [key]) else {
+            return key.to_string();
+        };
+        match &self.public_url {
+            Some(base) => format!("{base}{}", url.path()),
+            None => url.to_string(),
+        }
+    }
+
+    // Who is calling, for rate limits, quotas and ownership.
+    fn identity(&self, req: &HttpRequest) -> String {
+        self.proxies.identity(req, &self.api_keys)
     }
+
+    // The caller's identity when it authenticates with an issued API key.
+    // Reading and deleting links needs one: a client address is shared by
+    // everyone behind the same NAT, so it cannot prove ownership.
+    fn account(&self, req: &HttpRequest) -> Result<String, HttpResponse> {
+        self.api_keys.identity(req).ok_or_else(|| {
+            metrics::inc_error("unauthorized");
+            response::Problem::new(StatusCode::UNAUTHORIZED, "unauthorized")
+                .with_detail("an issued API key is required")
+                .error_response()
+        })
+    }
+
+    // The authenticated caller's own link. Links of other owners are reported
+    // as missing, so keys cannot be probed for their settings.
+    async fn owned(&self, req: &HttpRequest, name: &str) -> Result<model::Url, HttpResponse> {
+        let owner = self.account(req)?;
+        self.store
+            .fetch(name)
+            .await
+            .filter(|url| url.owner() == Some(owner.as_str()))
+            .ok_or_else(|| not_found(name))
+    }
+
+    // Click events are written in the background so analytics never slow
+    // down the redirect itself; failures are only logged.
+    fn record_click(&self, click: model::Click) {
+        let clicks = self.clicks.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(err) = clicks.record(&click).await {
+                log::warn!("recording click failed: {err}");
+            }
+        });
+    }
+
+    // Current consumption of `owner` against the configured quotas.
+    async fn usage(&self, owner: &str) -> Result<response::Usage, store::Error> {
+        let links = self.store.count_owned(owner, false).await?;
+        let custom_keys = self.store.count_owned(owner, true).await?;
+        Ok(response::Usage::new(
+            response::Quota::new(links, self.quota.max_links()),
+            response::Quota::new(custom_keys, self.quota.max_custom_keys()),
+        ))
+    }
+}
+
+// The cause is logged; the client only learns that something failed.
+fn internal_error(err: store::Error) -> HttpResponse {
+    log::error!("{err}");
+    metrics::inc_error("database");
+    err.error_response()
+}
+
+// Answer for links that do not exist or belong to someone else.
+fn not_found(name: &str) -> HttpResponse {
+    response::Problem::new(StatusCode::NOT_FOUND, "not_found")
+        .with_detail(format!("no such link: {name}"))
+        .error_response()
+}
+
+// Shared response for throttled clients.
+// `Retry-After` tells well-behaved clients how long to back off; it is left
+// out when the bucket never refills.
+fn throttled(limiter: &ratelimit::Limiter, client: &str) -> Option<HttpResponse> {
+    let wait = limiter.check(client).err()?;
+    log::warn!("rate limit exceeded for {client}");
+    metrics::inc_error("rate_limited");
+    Some(too_many_requests(wait))
+}
+
+fn too_many_requests(wait: std::time::Duration) -> HttpResponse {
+    let mut resp = response::Problem::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited")
+        .with_detail("rate limit exceeded")
+        .error_response();
+    if let Some(secs) = ratelimit::retry_after(wait) {
+        resp.headers_mut()
+            .insert(header::RETRY_AFTER, header::HeaderValue::from(secs));
+    }
+    resp
 }
 
 // Attribute macro: transforms the function into an HTTP POST handler.
 // Actix uses procedural macros to generate routing code at compile time.
+#[utoipa::path(
+    post,
+    path = "/urls",
+    tag = "links",
+    request_body = request::Url,
+    responses(
+        (
+            status = 201,
+            description = "The new link",
+            body = response::Shortened,
+            headers(("Location" = String, description = "Details of the new link"))
+        ),
+        (
+            status = 400,
+            description = "Invalid request",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "Quota exceeded",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 409,
+            description = "Key already exists",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 429,
+            description = "Rate limit exceeded",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
 #[post("/urls")]
 // `impl Responder` is a return-position impl trait - the function returns
 // some type that implements Responder, without specifying which concrete type.
-async fn create(data: web::Data<State>, url: web::Json<request::Url>) -> impl Responder {
+async fn create(
+    req: HttpRequest,
+    data: web::Data<State>,
+    url: web::Json<request::Url>,
+) -> impl Responder {
     // `log::info!` is a macro. The `{url:?}` uses Debug formatting (`:?`).
     log::info!("get {url:?}");
 
+    let owner = data.identity(&req);
+
+    if let Some(resp) = throttled(&data.create_limit, &owner) {
+        return resp;
+    }
+
     // `if let` destructures a pattern. Here it extracts Err variant.
     // More concise than full `match` when you only care about one variant.
     if let Err(err) = url.validate() {
         log::warn!("validation failed: {err}");
         metrics::inc_error("validation");
-        return HttpResponse::BadRequest().json(err.to_string());
+        return err.error_response();
     }
 
-    // `if-else` is an expression in Rust - it returns a value.
-    // Both branches must return the same type.
-    let name = if url.name() == "-" {
-        store::Url::random_key()
-    } else {
-        // `String::from()` creates an owned String from a string slice (&str).
-        String::from(url.name())
+    let custom = url.name() != "-";
+
+    // Quotas are checked before insert. Concurrent creates by the same owner
+    // may overshoot by a few links, which is acceptable here.
+    let mut usage = match data.usage(&owner).await {
+        Ok(usage) => usage,
+        Err(err) => return internal_error(err),
     };
+    if let Err(what) = usage.reserve(custom) {
+        log::warn!("quota exceeded for {owner}");
+        metrics::inc_error("quota_exceeded");
+        return response::Problem::new(StatusCode::FORBIDDEN, "quota_exceeded")
+            .with_detail(format!("quota exceeded: {what}"))
+            .error_response();
+    }
 
-    let m = model::Url::new(url.url(), name.as_str());
+    // Hashing a password with Argon2 takes tens of milliseconds, so links
+    // are built on the blocking thread pool.
+    let m = {
+        let (url, owner) = (url.into_inner(), owner.clone());
+        match web::block(move || link(&url, &owner)).await {
+            Ok(m) => m,
+            Err(err) => {
+                log::error!("{err}");
+                return response::Problem::internal().error_response();
+            }
+        }
+    };
     // `match` is exhaustive pattern matching - all variants must be handled.
     // `Ok(..)` uses `..` to ignore the inner value we don't need.
     match data.store.store(&m).await {
         Ok(..) => {
             // Increment custom metric for successful URL creation.
             metrics::inc_urls_created();
-            HttpResponse::Ok().json(m.key())
+            // `Location` points at the new link's details, wherever the
+            // service is mounted. `url_for` percent-encodes the key.
+            let mut created = HttpResponse::Created();
+            if let Ok(location) = req.url_for("show_link", [m.key()]) {
+                created.insert_header((header::LOCATION, location.path()));
+            }
+            created.json(response::Shortened::new(&m, data.short_url(&req, m.key())))
         }
         Err(err) => {
             // Check for duplicate key error and return 409 Conflict.
@@ -65,31 +345,863 @@ async fn create(data: web::Data<State>, url: web::Json<request::Url>) -> impl Re
             if err.is_duplicate_key() {
                 log::warn!("{err}");
                 metrics::inc_error("duplicate_key");
-                return HttpResponse::Conflict().json(err.to_string());
+                return err.error_response();
             }
-            log::error!("{err}");
-            metrics::inc_error("database");
-            HttpResponse::InternalServerError().json("Something went wrong")
+            internal_error(err)
         }
     }
 }
 
+// Stored link for a validated request.
+// Requests without a custom name get a random key.
+fn link(url: &request::Url, owner: &str) -> model::Url {
+    url.to_model(&url.key(), owner)
+}
+
+// Create many links in one call. Each item is validated and checked against
+// the quotas on its own; the valid ones go to the database in one unordered
+// `insert_many`, so a duplicate key only fails its own item. The response
+// lists one outcome per item, in request order.
+#[utoipa::path(
+    post,
+    path = "/urls/batch",
+    tag = "links",
+    request_body = Vec<request::Url>,
+    responses(
+        (
+            status = 200,
+            description = "One outcome per link, in request order",
+            body = Vec<response::Created>
+        ),
+        (
+            status = 400,
+            description = "Too many links",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 429,
+            description = "Rate limit exceeded",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[post("/urls/batch")]
+async fn create_batch(
+    req: HttpRequest,
+    data: web::Data<State>,
+    urls: web::Json<Vec<request::Url>>,
+) -> impl Responder {
+    let owner = data.identity(&req);
+
+    // The whole batch costs one token, like a single create.
+    if let Some(resp) = throttled(&data.create_limit, &owner) {
+        return resp;
+    }
+
+    if urls.len() > MAX_BATCH {
+        metrics::inc_error("validation");
+        return response::Problem::new(StatusCode::BAD_REQUEST, "batch_too_large")
+            .with_detail(format!("at most {MAX_BATCH} links per batch"))
+            .error_response();
+    }
+
+    let mut usage = match data.usage(&owner).await {
+        Ok(usage) => usage,
+        Err(err) => return internal_error(err),
+    };
+
+    // Items that pass validation and quotas are collected with their position
+    // so database results can be put back in place.
+    let mut results: Vec<response::Created> = Vec::with_capacity(urls.len());
+    let mut positions: Vec<usize> = Vec::new();
+    for (index, url) in urls.iter().enumerate() {
+        if let Err(err) = url.validate() {
+            metrics::inc_error("validation");
+            results.push(response::Created::error(&err));
+            continue;
+        }
+        if let Err(what) = usage.reserve(url.name() != "-") {
+            metrics::inc_error("quota_exceeded");
+            results.push(response::Created::error(
+                response::Problem::new(StatusCode::FORBIDDEN, "quota_exceeded")
+                    .with_detail(format!("quota exceeded: {what}")),
+            ));
+            continue;
+        }
+        // Placeholder, replaced once the insert has run.
+        results.push(response::Created::error(response::Problem::internal()));
+        positions.push(index);
+    }
+
+    // Built on the blocking thread pool, as in `create`: passwords are hashed here.
+    let models = {
+        let (urls, positions, owner) = (urls.into_inner(), positions.clone(), owner.clone());
+        let build = move || -> Vec<model::Url> {
+            positions
+                .iter()
+                .map(|&index| link(&urls[index], &owner))
+                .collect()
+        };
+        match web::block(build).await {
+            Ok(models) => models,
+            Err(err) => {
+                log::error!("{err}");
+                return response::Problem::internal().error_response();
+            }
+        }
+    };
+
+    let stored = match data.store.store_many(&models).await {
+        Ok(stored) => stored,
+        Err(err) => return internal_error(err),
+    };
+    // `zip` pairs each inserted link with its position and insert result.
+    for ((index, m), result) in positions.into_iter().zip(&models).zip(stored) {
+        results[index] = match result {
+            Ok(()) => {
+                metrics::inc_urls_created();
+                response::Created::key(m.key())
+            }
+            Err(err) => {
+                if err.is_duplicate_key() {
+                    metrics::inc_error("duplicate_key");
+                }
+                response::Created::error(&err)
+            }
+        };
+    }
+
+    HttpResponse::Ok().json(results)
+}
+
+// Two path segments, so it never collides with the `/{name}` redirect route.
+#[utoipa::path(
+    get,
+    path = "/urls/usage",
+    tag = "links",
+    responses((status = 200, description = "The caller's quota usage", body = response::Usage))
+)]
+#[get("/urls/usage")]
+async fn show_usage(req: HttpRequest, data: web::Data<State>) -> impl Responder {
+    let owner = data.identity(&req);
+
+    match data.usage(&owner).await {
+        Ok(usage) => HttpResponse::Ok().json(usage),
+        Err(err) => internal_error(err),
+    }
+}
+
+// Reports are scoped to the caller, like quotas.
+#[utoipa::path(
+    get,
+    path = "/urls/campaigns",
+    tag = "links",
+    responses(
+        (status = 200, description = "Links per UTM campaign", body = Vec<response::Campaign>),
+        (
+            status = 401,
+            description = "No issued API key",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[get("/urls/campaigns")]
+async fn campaigns(req: HttpRequest, data: web::Data<State>) -> impl Responder {
+    let owner = match data.account(&req) {
+        Ok(owner) => owner,
+        Err(resp) => return resp,
+    };
+
+    match data.store.campaigns(&owner).await {
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.into_iter()
+                .map(|(campaign, links)| response::Campaign::new(campaign, links))
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => internal_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/urls/campaigns/{campaign}",
+    tag = "links",
+    params(
+        ("campaign" = String, Path, description = "UTM campaign"),
+        (
+            "limit" = Option<u32>,
+            Query,
+            description = "Most links returned, 20 by default and at most 1000"
+        ),
+    ),
+    responses(
+        (status = 200, description = "Newest links of the campaign", body = Vec<response::Link>),
+        (
+            status = 401,
+            description = "No issued API key",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[get("/urls/campaigns/{campaign}")]
+async fn campaign_links(
+    req: HttpRequest,
+    data: web::Data<State>,
+    campaign: web::Path<String>,
+    // Same paging as the link listing.
+    query: web::Query<request::List>,
+) -> impl Responder {
+    let owner = match data.account(&req) {
+        Ok(owner) => owner,
+        Err(resp) => return resp,
+    };
+
+    match data
+        .store
+        .by_campaign(&owner, &campaign, query.limit())
+        .await
+    {
+        Ok(urls) => {
+            HttpResponse::Ok().json(urls.iter().map(response::Link::new).collect::<Vec<_>>())
+        }
+        Err(err) => internal_error(err),
+    }
+}
+
+// The caller's links, newest first.
+#[utoipa::path(
+    get,
+    path = "/urls",
+    tag = "links",
+    params((
+        "limit" = Option<u32>,
+        Query,
+        description = "Most links returned, 20 by default and at most 1000"
+    )),
+    responses(
+        (status = 200, description = "The caller's newest links", body = Vec<response::Link>),
+        (
+            status = 401,
+            description = "No issued API key",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[get("/urls")]
+async fn list_links(
+    req: HttpRequest,
+    data: web::Data<State>,
+    query: web::Query<request::List>,
+) -> impl Responder {
+    let owner = match data.account(&req) {
+        Ok(owner) => owner,
+        Err(resp) => return resp,
+    };
+
+    match data.store.list(Some(&owner), query.limit()).await {
+        Ok(urls) => {
+            HttpResponse::Ok().json(urls.iter().map(response::Link::new).collect::<Vec<_>>())
+        }
+        Err(err) => internal_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/urls/{name}",
+    tag = "links",
+    params(("name" = String, Path, description = "Key of the link")),
+    responses(
+        (status = 200, description = "Settings of the link", body = response::Details),
+        (
+            status = 401,
+            description = "No issued API key",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "No such link of the caller",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[get("/urls/{name}")]
+async fn show_link(
+    req: HttpRequest,
+    data: web::Data<State>,
+    name: web::Path<String>,
+) -> impl Responder {
+    match data.owned(&req, &name).await {
+        Ok(url) => HttpResponse::Ok().json(response::Details::new(&url)),
+        Err(resp) => resp,
+    }
+}
+
+// The link's recorded clicks go with it, so a new link under the same key
+// starts without them.
+#[utoipa::path(
+    delete,
+    path = "/urls/{name}",
+    tag = "links",
+    params(("name" = String, Path, description = "Key of the link")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (
+            status = 401,
+            description = "No issued API key",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "No such link of the caller",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[delete("/urls/{name}")]
+async fn delete_link(
+    req: HttpRequest,
+    data: web::Data<State>,
+    name: web::Path<String>,
+) -> impl Responder {
+    if let Err(resp) = data.owned(&req, &name).await {
+        return resp;
+    }
+
+    match data.store.delete(&name).await {
+        Ok(true) => {}
+        // Deleted by a concurrent request in the meantime.
+        Ok(false) => return not_found(&name),
+        Err(err) => return internal_error(err),
+    }
+    match data.clicks.delete_key(&name).await {
+        Ok(..) => HttpResponse::NoContent().finish(),
+        Err(err) => internal_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/urls/{name}/stats",
+    tag = "links",
+    params(("name" = String, Path, description = "Key of the link")),
+    responses(
+        (status = 200, description = "Click totals of the link", body = response::Stats),
+        (
+            status = 401,
+            description = "No issued API key",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "No such link of the caller",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[get("/urls/{name}/stats")]
+async fn link_stats(
+    req: HttpRequest,
+    data: web::Data<State>,
+    name: web::Path<String>,
+) -> impl Responder {
+    let url = match data.owned(&req, &name).await {
+        Ok(url) => url,
+        Err(resp) => return resp,
+    };
+
+    match data.clicks.by_country(url.key()).await {
+        Ok(rows) => HttpResponse::Ok().json(response::Stats::new(url.key(), rows)),
+        Err(err) => internal_error(err),
+    }
+}
+
+// QR code of a link's short URL. The image only depends on the URL and the
+// query options, so its hash doubles as a strong `ETag`.
+#[utoipa::path(
+    get,
+    path = "/urls/{name}/qr",
+    tag = "links",
+    params(
+        ("name" = String, Path, description = "Key of the link"),
+        ("format" = Option<String>, Query, description = "`png` (default) or `svg`"),
+        ("size" = Option<u32>, Query, description = "Edge length in pixels, 64 to 2048"),
+        ("ecc" = Option<String>, Query, description = "Error correction level: L, M, Q or H"),
+        ("margin" = Option<u32>, Query, description = "Quiet zone in modules, at most 16"),
+        ("fg" = Option<String>, Query, description = "Foreground colour, e.g. `#000000`"),
+        ("bg" = Option<String>, Query, description = "Background colour, e.g. `#ffffff`"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "QR code of the short URL",
+            content((String = "image/png"), (String = "image/svg+xml"))
+        ),
+        (status = 304, description = "Unchanged since the given `ETag`"),
+        (
+            status = 400,
+            description = "Invalid options",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "No such link",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[get("/urls/{name}/qr")]
+async fn qr_code(
+    req: HttpRequest,
+    data: web::Data<State>,
+    name: web::Path<String>,
+    options: web::Query<request::Qr>,
+) -> impl Responder {
+    if let Some(resp) = throttled(&data.redirect_limit, &data.identity(&req)) {
+        return resp;
+    }
+
+    if let Err(err) = options.validate() {
+        metrics::inc_error("validation");
+        return err.error_response();
+    }
+
+    let Some(url) = data.store.fetch(name.as_str()).await else {
+        return not_found(&name);
+    };
+
+    let short = data.short_url(&req, url.key());
+    let style = options.style();
+    let format = options.format();
+
+    // The ETag is derived from what would be rendered, so revalidation is
+    // answered without drawing the image.
+    let etag = header::EntityTag::new_strong(qr::fingerprint(&short, &style, format));
+    let cache = format!("public, max-age={QR_MAX_AGE}");
+    // `If-None-Match` uses weak comparison, and `*` matches any existing resource.
+    let fresh = match req.get_header::<header::IfNoneMatch>() {
+        Some(header::IfNoneMatch::Any) => true,
+        Some(header::IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(&etag)),
+        None => false,
+    };
+    if fresh {
+        return HttpResponse::NotModified()
+            .insert_header(header::ETag(etag))
+            .insert_header((header::CACHE_CONTROL, cache))
+            .finish();
+    }
+
+    // Rendering a large code is CPU-bound, so it runs on the blocking thread pool.
+    let render = move || match format {
+        qr::Format::Png => qr::png(&short, &style),
+        qr::Format::Svg => qr::svg(&short, &style).map(String::into_bytes),
+    };
+    let body = match web::block(render).await {
+        Ok(Ok(body)) => body,
+        Ok(Err(err)) => {
+            log::error!("{err}");
+            return response::Problem::internal().error_response();
+        }
+        Err(err) => {
+            log::error!("{err}");
+            return response::Problem::internal().error_response();
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(header::ETag(etag))
+        .insert_header((header::CACHE_CONTROL, cache))
+        .body(body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{name}",
+    tag = "redirects",
+    params((
+        "name" = String,
+        Path,
+        description = "Key of the link, with a trailing `+` for its preview page"
+    )),
+    responses(
+        (status = 307, description = "Redirect to the destination; the status is configurable"),
+        (
+            status = 200,
+            description = "Preview, Open Graph or password page",
+            content_type = "text/html"
+        ),
+        (status = 404, description = "No such link"),
+        (status = 410, description = "Expired or out of clicks"),
+        (
+            status = 429,
+            description = "Rate limit exceeded",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
 #[get("/{name}")]
 // `web::Path<String>` extracts path parameters. Actix deserializes `{name}` from URL.
-async fn fetch(data: web::Data<State>, name: web::Path<String>) -> impl Responder {
+async fn fetch(
+    req: HttpRequest,
+    data: web::Data<State>,
+    name: web::Path<String>,
+) -> impl Responder {
+    redirect(&req, &data, name.as_str(), None, None).await
+}
+
+// `{tail:.*}` is a regex segment matching the rest of the path, slashes included.
+// Only links with `forward_path` accept it; others answer 404 as before.
+#[get("/{name}/{tail:.*}")]
+async fn fetch_path(
+    req: HttpRequest,
+    data: web::Data<State>,
+    // Tuple extraction: path segments are deserialized in declaration order.
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (name, tail) = path.into_inner();
+    redirect(&req, &data, &name, Some(&tail), None).await
+}
+
+// Password form submissions. `web::Form` decodes the
+// `application/x-www-form-urlencoded` body posted by the page.
+#[utoipa::path(
+    post,
+    path = "/{name}",
+    tag = "redirects",
+    params(("name" = String, Path, description = "Key of a protected link")),
+    request_body(
+        content_type = "application/x-www-form-urlencoded",
+        description = "`password` field of the password page"
+    ),
+    responses(
+        (status = 303, description = "Redirect to the destination"),
+        (
+            status = 403,
+            description = "Wrong password, the page is shown again",
+            content_type = "text/html"
+        ),
+        (
+            status = 429,
+            description = "Too many attempts",
+            body = response::Problem,
+            content_type = "application/problem+json"
+        ),
+        (status = 404, description = "No such link"),
+    )
+)]
+#[post("/{name}")]
+async fn unlock(
+    req: HttpRequest,
+    data: web::Data<State>,
+    name: web::Path<String>,
+    form: web::Form<request::Unlock>,
+) -> impl Responder {
+    redirect(&req, &data, name.as_str(), None, Some(form.password())).await
+}
+
+#[post("/{name}/{tail:.*}")]
+async fn unlock_path(
+    req: HttpRequest,
+    data: web::Data<State>,
+    path: web::Path<(String, String)>,
+    form: web::Form<request::Unlock>,
+) -> impl Responder {
+    let (name, tail) = path.into_inner();
+    redirect(&req, &data, &name, Some(&tail), Some(form.password())).await
+}
+
+// Password page for protected links. `no-store` keeps it out of shared caches.
+fn password_page(name: &str, status: StatusCode, failed: bool) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type(ContentType::html())
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .body(page::password(name, failed))
+}
+
+// Shared by the redirect routes. `suffix` is the extra path after the key and
+// `password` is the secret submitted through the password page, if any.
+async fn redirect(
+    req: &HttpRequest,
+    data: &State,
+    name: &str,
+    suffix: Option<&str>,
+    password: Option<&str>,
+) -> HttpResponse {
     log::info!("get {name}");
 
-    let url = data.store.fetch(name.as_str()).await;
+    // Appending `+` to a key asks for the preview page of any link.
+    let (name, inspect) = match name.strip_suffix('+') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+
+    if let Some(resp) = throttled(&data.redirect_limit, &data.identity(req)) {
+        return resp;
+    }
 
-    // Pattern matching on Option<T>: Some(value) or None.
-    // Option is Rust's way of handling nullable values safely.
-    match url {
-        Some(url) => HttpResponse::TemporaryRedirect()
-            // Tuple syntax `(a, b)` creates an anonymous pair.
-            .insert_header((header::LOCATION, url.url()))
-            .finish(),
-        None => HttpResponse::NotFound().finish(),
+    // `let ... else` binds on match or diverges - here returning 404 early.
+    let Some(url) = data.store.fetch(name).await else {
+        return HttpResponse::NotFound().finish();
+    };
+    if suffix.is_some() && !url.forward_path() {
+        return HttpResponse::NotFound().finish();
     }
+    let now = DateTime::now();
+    // Outside the activation window: send visitors to the fallback if there is
+    // one, otherwise 404 before launch (it does not exist yet) and 410 after.
+    let window = url.window(now);
+    if window != model::Window::Active {
+        if let Some(fallback) = url.fallback() {
+            return HttpResponse::TemporaryRedirect()
+                .insert_header((header::LOCATION, fallback))
+                .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+                .finish();
+        }
+        return match window {
+            model::Window::Pending => HttpResponse::NotFound().finish(),
+            _ => HttpResponse::Gone().finish(),
+        };
+    }
+
+    // Exhausted links are gone for good - no point asking for a password.
+    if url.max_clicks() == Some(0) {
+        return HttpResponse::Gone().finish();
+    }
+
+    // Chat apps fetch pasted links to build a preview card. They get the
+    // metadata page instead of the redirect, and their fetch is not a visit,
+    // so it neither spends clicks nor shows up in analytics.
+    if url.has_open_graph() && useragent::is_unfurler(user_agent(req)) {
+        let short = data.short_url(req, url.key());
+        return HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+            .body(page::open_graph(
+                &short,
+                url.title(),
+                url.description(),
+                url.image(),
+            ));
+    }
+
+    if let Some(hash) = url.password() {
+        // `let ... else` again: without a submitted password, show the form.
+        let Some(given) = password else {
+            return password_page(name, StatusCode::OK, false);
+        };
+        // Only failures spend tokens, but an empty bucket blocks every attempt.
+        if let Err(wait) = data.password_limit.peek(name) {
+            log::warn!("too many failed password attempts for {name}");
+            metrics::inc_error("rate_limited");
+            return too_many_requests(wait);
+        }
+        // Argon2 takes tens of milliseconds, so it runs on the blocking
+        // thread pool rather than stalling this worker's other requests.
+        let (hash, given) = (hash.clone(), given.to_string());
+        let verified = match web::block(move || hash.verify(&given)).await {
+            Ok(verified) => verified,
+            Err(err) => {
+                log::error!("{err}");
+                return response::Problem::internal().error_response();
+            }
+        };
+        if !verified {
+            // The bucket was just peeked, so the result of spending is irrelevant.
+            let _ = data.password_limit.check(name);
+            metrics::inc_error("wrong_password");
+            return password_page(name, StatusCode::FORBIDDEN, true);
+        }
+    }
+
+    // The stored counter is authoritative: the copy fetched above may be stale
+    // when other requests consumed the last click in the meantime.
+    if url.max_clicks().is_some() {
+        match data.store.consume(name).await {
+            Ok(true) => {}
+            Ok(false) => return HttpResponse::Gone().finish(),
+            Err(err) => return internal_error(err),
+        }
+    }
+
+    // The country feeds both country rules and click analytics.
+    let country = data.proxies.ip(req).and_then(|ip| data.geoip.country(ip));
+
+    // Visitor rules come first, then the negotiated language; split links
+    // then rotate among their targets, and everything else uses `url`.
+    let rule = if url.rules().is_empty() {
+        None
+    } else {
+        let visitor = useragent::parse(user_agent(req)).with_country(country.clone());
+        model::first_match(url.rules(), &visitor)
+    };
+    let chosen = rule.map(model::Rule::url).or_else(|| localized(req, &url));
+    // A matched rule or language overrides the split, so no variant is served.
+    let variant = if chosen.is_none() {
+        pick_target(req, &url)
+    } else {
+        None
+    };
+    let base = match chosen {
+        Some(chosen) => chosen,
+        None => variant
+            .as_ref()
+            .and_then(|(index, _)| url.targets().get(*index))
+            .map_or(url.url(), model::Target::url),
+    };
+
+    let query = Some(req.query_string()).filter(|_| url.forward_query());
+    let location = destination::build(base, suffix, query);
+
+    // `u32::try_from` cannot fail in practice - links hold a handful of targets.
+    let index = variant
+        .as_ref()
+        .and_then(|(index, _)| u32::try_from(*index).ok());
+    if let Some(index) = index {
+        metrics::inc_variant_redirect(index);
+    }
+    data.record_click(model::Click::new(name, index).with_country(country));
+
+    // The preview stands in for the redirect, so the visit is counted above
+    // and the page is tied to this visitor.
+    if inspect || data.preview || url.preview() {
+        let mut resp = HttpResponse::Ok();
+        if let Some((_, Some(cookie))) = &variant {
+            resp.cookie(cookie.clone());
+        }
+        return resp
+            .content_type(ContentType::html())
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .body(page::preview(name, &location));
+    }
+
+    // A successful password POST must be answered with 303 See Other so the
+    // browser follows it with a GET instead of re-posting the form to the
+    // destination, and it must never be cached or the password is bypassed.
+    let mut resp = if url.password().is_some() {
+        let mut resp = HttpResponse::SeeOther();
+        resp.insert_header((header::CACHE_CONTROL, "no-store"));
+        resp
+    } else {
+        // `unwrap_or` picks the server default when the link has no preference.
+        let redirect = url.redirect().unwrap_or(data.redirect);
+        // Every `Redirect` variant is a valid 3xx code, so `from_u16` cannot fail.
+        let mut resp = HttpResponse::build(
+            StatusCode::from_u16(redirect.status()).expect("redirect status is valid"),
+        );
+        resp.insert_header((
+            header::CACHE_CONTROL,
+            cache_control(&url, redirect, data.max_age, now),
+        ));
+        resp
+    };
+
+    if let Some((_, Some(cookie))) = variant {
+        resp.cookie(cookie);
+    }
+
+    resp
+        // Tuple syntax `(a, b)` creates an anonymous pair.
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+// Permanent redirects are meant to be remembered; temporary ones
+// must be revalidated so changes to the link take effect.
+fn cache_control(
+    url: &model::Url,
+    redirect: model::Redirect,
+    max_age: u64,
+    now: DateTime,
+) -> String {
+    // Click-limited links are never cached, or cached hits would bypass the count.
+    if url.max_clicks().is_some() {
+        return String::from("no-store");
+    }
+    // Split links must be re-rolled on every visit and rule-based links
+    // depend on the visitor.
+    if !redirect.is_permanent() || url.is_dynamic() {
+        return String::from("private, no-cache");
+    }
+    // Caches must forget the redirect once the activation window closes.
+    let max_age = match url.not_after() {
+        Some(end) => {
+            let left = (end.timestamp_millis() - now.timestamp_millis()) / 1000;
+            max_age.min(u64::try_from(left).unwrap_or(0))
+        }
+        None => max_age,
+    };
+    format!("public, max-age={max_age}")
+}
+
+// Name of the cookie pinning a visitor to one target of a sticky split link.
+// Keys may hold characters that are not allowed in cookie names, hence hex.
+fn variant_cookie(key: &str) -> String {
+    format!("fesghel_{}", hex::encode(key))
+}
+
+// A missing or non-UTF-8 header reads as empty, which classifies as a bot.
+fn user_agent(req: &HttpRequest) -> &str {
+    req.headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+}
+
+// Destination negotiated from `Accept-Language`, if the link is localized
+// and the visitor prefers one of its languages.
+fn localized<'a>(req: &HttpRequest, url: &'a model::Url) -> Option<&'a str> {
+    if url.languages().is_empty() {
+        return None;
+    }
+    let accept = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    model::negotiate(accept, url.languages()).map(model::Localized::url)
+}
+
+// Pick the target of a split link. Returns its index and, when a sticky link
+// sees a new visitor, the cookie that keeps them on it.
+fn pick_target(req: &HttpRequest, url: &model::Url) -> Option<(usize, Option<Cookie<'static>>)> {
+    let targets = url.targets();
+    let name = variant_cookie(url.key());
+
+    if url.sticky() {
+        // Ignore cookies that point at targets removed or weighted out since.
+        let pinned = req
+            .cookie(&name)
+            .and_then(|c| c.value().parse::<usize>().ok())
+            .filter(|&i| targets.get(i).is_some_and(|t| t.weight() > 0));
+        if let Some(index) = pinned {
+            return Some((index, None));
+        }
+    }
+
+    let index = model::choose(targets, &mut rand::rng())?;
+    let cookie = url.sticky().then(|| {
+        Cookie::build(name, index.to_string())
+            .path("/")
+            .max_age(CookieDuration::days(STICKY_DAYS))
+            .http_only(true)
+            .finish()
+    });
+    Some((index, cookie))
 }
 
 // `pub fn` makes this function public (accessible from other modules).
@@ -97,5 +1209,41 @@ async fn fetch(data: web::Data<State>, name: web::Path<String>) -> impl Responde
 pub fn register(state: State, scope: Scope) -> Scope {
     // `web::Data` wraps state in Arc for thread-safe shared ownership.
     let data = web::Data::new(state);
-    scope.app_data(data).service(create).service(fetch)
+    scope
+        .app_data(data)
+        .service(create)
+        .service(create_batch)
+        .service(list_links)
+        .service(show_usage)
+        .service(campaigns)
+        .service(campaign_links)
+        // Registered before `fetch_path`, whose `{tail}` would match them too.
+        .service(qr_code)
+        .service(link_stats)
+        .service(show_link)
+        .service(delete_link)
+        .service(fetch)
+        .service(fetch_path)
+        .service(unlock)
+        .service(unlock_path)
 }
+
+// Routes of this module for the OpenAPI document, relative to `/api`.
+// The catch-all `{tail}` variants are left out; they behave like their
+// single-segment counterparts.
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(
+    create,
+    create_batch,
+    list_links,
+    show_usage,
+    campaigns,
+    campaign_links,
+    qr_code,
+    link_stats,
+    show_link,
+    delete_link,
+    fetch,
+    unlock
+))]
+pub struct Api;
@@ -0,0 +1,297 @@
+// Adapters for the export layouts of other shorteners.
+// Each one renames a row's fields to the ones `Row::from_fields` expects -
+// `key`, `url`, `created_at` plus create request fields - and normalises
+// timestamps and tags on the way. Fields without a counterpart are dropped.
+
+use mongodb::bson::DateTime;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    // Our own layout, as written by the export; rows pass through unchanged.
+    #[default]
+    Fesghel,
+    // `yourls_url` table dumps: keyword, url, title, timestamp.
+    Yourls,
+    // Kutt links: address, target, description, created_at, expire_in.
+    Kutt,
+    // Shlink short URLs: shortCode, longUrl, dateCreated, title, tags, meta.
+    Shlink,
+    // Bitly bitlinks: id/link, long_url, title, created_at, custom_bitlinks, tags.
+    Bitly,
+}
+
+impl std::str::FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fesghel" => Ok(Source::Fesghel),
+            "yourls" => Ok(Source::Yourls),
+            "kutt" => Ok(Source::Kutt),
+            "shlink" => Ok(Source::Shlink),
+            "bitly" => Ok(Source::Bitly),
+            _ => Err(format!("unknown import source: {s}")),
+        }
+    }
+}
+
+/// Map a row of `source`'s export onto our field names.
+pub fn adapt(source: Source, fields: Map<String, Value>) -> Map<String, Value> {
+    if source == Source::Fesghel {
+        return fields;
+    }
+
+    // Column names differ in case and spacing between API dumps and CSV
+    // downloads ("longUrl", "Long URL", "long_url"), so compare them squashed.
+    let fields: Map<String, Value> = fields
+        .into_iter()
+        .map(|(name, value)| (squash(&name), value))
+        .collect();
+    let get = |names: &[&str]| names.iter().find_map(|n| fields.get(*n)).cloned();
+
+    let mut out = Map::new();
+    // Exports use nulls and empty cells alike for "no value".
+    let mut put = |name: &str, value: Option<Value>| {
+        if let Some(value) = value.filter(|v| !v.is_null() && v.as_str() != Some("")) {
+            out.insert(name.to_string(), value);
+        }
+    };
+
+    match source {
+        Source::Fesghel => unreachable!("handled above"),
+        Source::Yourls => {
+            put("key", get(&["keyword"]));
+            put("url", get(&["url"]));
+            put("title", get(&["title"]));
+            put("created_at", get(&["timestamp"]).map(timestamp));
+        }
+        Source::Kutt => {
+            put("key", get(&["address"]));
+            put("url", get(&["target"]));
+            put("description", get(&["description"]));
+            put("created_at", get(&["createdat"]).map(timestamp));
+            put("not_after", get(&["expirein"]).map(timestamp));
+        }
+        Source::Shlink => {
+            // CSV downloads only have the full short URL.
+            let key = get(&["shortcode"]).or_else(|| get(&["shorturl"]).map(last_segment));
+            // The API nests the limits under `meta`.
+            let meta = |name: &str| {
+                get(&["meta"])
+                    .and_then(|m| m.get(name).cloned())
+                    .or_else(|| get(&[&name.to_lowercase()]))
+            };
+            put("key", key);
+            put("url", get(&["longurl"]));
+            put("title", get(&["title"]));
+            put(
+                "created_at",
+                get(&["datecreated", "createdat"]).map(timestamp),
+            );
+            put("tags", get(&["tags"]).map(tags));
+            put("not_before", meta("validSince").map(timestamp));
+            put("not_after", meta("validUntil").map(timestamp));
+            put("max_clicks", meta("maxVisits").map(number));
+        }
+        Source::Bitly => {
+            // A custom back-half is the key people know; the generated hash
+            // in `id` ("bit.ly/3xYz") is the fallback.
+            let custom = get(&["custombitlinks", "custombackhalf", "custombackhalfs"])
+                .and_then(|v| match v {
+                    Value::Array(items) => items.into_iter().next(),
+                    Value::String(s) => s.split([',', '|']).next().map(Value::from),
+                    _ => None,
+                })
+                .filter(|v| v.as_str().is_some_and(|s| !s.trim().is_empty()));
+            put("custom", custom.is_some().then_some(Value::Bool(true)));
+            let key = custom
+                .or_else(|| get(&["id", "bitlink", "link"]))
+                .map(last_segment);
+            put("key", key);
+            put("url", get(&["longurl"]));
+            put("title", get(&["title"]));
+            put(
+                "created_at",
+                get(&["createdat", "datecreated"]).map(timestamp),
+            );
+            put("tags", get(&["tags"]).map(tags));
+        }
+    }
+    out
+}
+
+fn squash(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+// "https://bit.ly/abc" and "bit.ly/abc" both become "abc".
+fn last_segment(value: Value) -> Value {
+    match value.as_str() {
+        Some(text) => {
+            let text = text.trim().trim_end_matches('/');
+            Value::from(text.rsplit('/').next().unwrap_or(text))
+        }
+        None => value,
+    }
+}
+
+// Tags come as JSON arrays or as one cell separated by commas or pipes.
+pub(super) fn tags(value: Value) -> Value {
+    match value {
+        Value::String(text) => text
+            .split([',', '|'])
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(Value::from)
+            .collect(),
+        other => other,
+    }
+}
+
+// CSV cells are text; limits must be numbers.
+fn number(value: Value) -> Value {
+    match value.as_str().and_then(|s| s.trim().parse::<u64>().ok()) {
+        Some(n) => Value::from(n),
+        None => value,
+    }
+}
+
+// Rewrite timestamps as RFC 3339. Values that cannot be read are passed on
+// unchanged, so validation reports them against their line.
+fn timestamp(value: Value) -> Value {
+    match value.as_str().and_then(rfc3339) {
+        Some(text) => Value::from(text),
+        None => value,
+    }
+}
+
+// Accepts RFC 3339 plus the variants found in the wild: a space instead of
+// `T` (YOURLS, stored without zone and read as UTC) and offsets without a
+// colon such as `+0000` (Bitly).
+fn rfc3339(text: &str) -> Option<String> {
+    let text = text.trim();
+    let (date, time) = text.split_once(['T', ' '])?;
+    let mut time = time.to_string();
+
+    let has_zone = time.ends_with('Z') || time.contains(['+', '-']);
+    if !has_zone {
+        time.push('Z');
+    } else if let Some(at) = time.rfind(['+', '-']) {
+        let offset = &time[at + 1..];
+        if offset.len() == 4 && offset.chars().all(|c| c.is_ascii_digit()) {
+            time.insert(at + 3, ':');
+        }
+    }
+
+    DateTime::parse_rfc3339_str(format!("{date}T{time}"))
+        .ok()?
+        .try_to_rfc3339_string()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn fesghel_rows_pass_through() {
+        let row = fields(r#"{"key":"a","url":"https://example.com","sticky":true}"#);
+        assert_eq!(adapt(Source::Fesghel, row.clone()), row);
+    }
+
+    #[test]
+    fn yourls_row() {
+        let row = fields(
+            r#"{"keyword":"abc","url":"https://example.com","title":"Ex","timestamp":"2021-03-04 05:06:07","ip":"127.0.0.1","clicks":"4"}"#,
+        );
+        assert_eq!(
+            adapt(Source::Yourls, row),
+            fields(
+                r#"{"key":"abc","url":"https://example.com","title":"Ex","created_at":"2021-03-04T05:06:07Z"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn kutt_row() {
+        let row = fields(
+            r#"{"address":"abc","target":"https://example.com","description":null,"created_at":"2021-03-04T05:06:07.000Z","expire_in":"2030-01-01T00:00:00Z","visit_count":3}"#,
+        );
+        assert_eq!(
+            adapt(Source::Kutt, row),
+            fields(
+                r#"{"key":"abc","url":"https://example.com","created_at":"2021-03-04T05:06:07Z","not_after":"2030-01-01T00:00:00Z"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn shlink_api_row() {
+        let row = fields(
+            r#"{"shortCode":"abc","longUrl":"https://example.com","dateCreated":"2021-03-04T05:06:07+02:00","tags":["a","b"],"meta":{"validSince":null,"validUntil":null,"maxVisits":10}}"#,
+        );
+        assert_eq!(
+            adapt(Source::Shlink, row),
+            fields(
+                r#"{"key":"abc","url":"https://example.com","created_at":"2021-03-04T03:06:07Z","tags":["a","b"],"max_clicks":10}"#
+            )
+        );
+    }
+
+    #[test]
+    fn shlink_csv_row() {
+        let row = fields(
+            r#"{"createdAt":"2021-03-04T05:06:07+00:00","shortUrl":"https://s.test/abc","longUrl":"https://example.com","title":"","tags":"a|b","visits":"3"}"#,
+        );
+        let out = adapt(Source::Shlink, row);
+        assert_eq!(out["key"], "abc");
+        assert_eq!(out["tags"], serde_json::json!(["a", "b"]));
+        assert!(!out.contains_key("title"));
+    }
+
+    #[test]
+    fn bitly_api_row() {
+        let row = fields(
+            r#"{"id":"bit.ly/3xYz","link":"https://bit.ly/3xYz","long_url":"https://example.com","title":"Ex","created_at":"2021-03-04T05:06:07+0000","custom_bitlinks":["https://bit.ly/launch"],"tags":["q3"]}"#,
+        );
+        assert_eq!(
+            adapt(Source::Bitly, row),
+            fields(
+                r#"{"custom":true,"key":"launch","url":"https://example.com","title":"Ex","created_at":"2021-03-04T05:06:07Z","tags":["q3"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn bitly_csv_row_without_custom_back_half() {
+        let row = fields(
+            r#"{"Bitlink":"bit.ly/3xYz","Long URL":"https://example.com","Date Created":"2021-03-04 05:06:07","Custom Back-half":"","Tags":"a, b"}"#,
+        );
+        let out = adapt(Source::Bitly, row);
+        assert_eq!(out["key"], "3xYz");
+        assert_eq!(out["url"], "https://example.com");
+        assert_eq!(out["tags"], serde_json::json!(["a", "b"]));
+    }
+
+    #[test]
+    fn unreadable_timestamps_pass_through() {
+        assert_eq!(timestamp(Value::from("yesterday")), "yesterday");
+        assert_eq!(rfc3339("2021-03-04"), None);
+    }
+
+    #[test]
+    fn source_from_str() {
+        assert_eq!("bitly".parse::<Source>(), Ok(Source::Bitly));
+        assert!("tinyurl".parse::<Source>().is_err());
+    }
+}
@@ -0,0 +1,729 @@
+// Bulk import of links, e.g. when migrating from another shortener.
+// Input is read line by line - CSV with a header row, or JSON Lines - so
+// files with millions of links never have to fit in memory. Rows are
+// validated like API requests, keep their original keys and are inserted
+// in chunks with one `insert_many` each.
+
+use std::collections::HashMap;
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use crate::model;
+use crate::request;
+use crate::store;
+
+mod adapter;
+
+pub use adapter::Source;
+
+// Rows inserted per `insert_many`.
+const CHUNK: usize = 1000;
+// Problems, and links planned by a dry run, listed one by one in a report;
+// further ones are only counted.
+const MAX_PROBLEMS: usize = 1000;
+// Request fields that are not text. CSV cells for them are read as JSON
+// literals, so `true` and `308` arrive as a boolean and a number.
+const TYPED_COLUMNS: &[&str] = &[
+    "redirect",
+    "max_clicks",
+    "forward_query",
+    "forward_path",
+    "preview",
+    "sticky",
+    "custom",
+    "protected",
+];
+// Owner of imported links unless another one is given, or a row has one.
+pub const DEFAULT_OWNER: &str = "import";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+// `FromStr` enables `"jsonl".parse::<Format>()` for command-line arguments.
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "jsonl" => Ok(Format::Jsonl),
+            _ => Err(format!("unknown import format: {s}")),
+        }
+    }
+}
+
+// A line that could not be imported.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct Problem {
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    error: String,
+}
+
+impl Problem {
+    fn new(line: usize, key: Option<&str>, error: impl ToString) -> Self {
+        Problem {
+            line,
+            key: key.map(String::from),
+            error: error.to_string(),
+        }
+    }
+}
+
+// A link a dry run would create.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct Planned {
+    line: usize,
+    key: String,
+    url: String,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct Report {
+    // Nothing was written; `imported` counts the links that would have been.
+    dry_run: bool,
+    // Last input line handled; pass it as `skip` to resume after an interruption.
+    lines: usize,
+    imported: u64,
+    // Keys already stored with the same URL, e.g. from an interrupted run.
+    existing: u64,
+    // Keys already stored with a different URL.
+    conflicts: u64,
+    invalid: u64,
+    problems: Vec<Problem>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    planned: Vec<Planned>,
+}
+
+impl Report {
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    fn problem(&mut self, problem: Problem) {
+        if self.problems.len() < MAX_PROBLEMS {
+            self.problems.push(problem);
+        }
+    }
+
+    fn plan(&mut self, line: usize, link: &model::Url) {
+        if self.planned.len() < MAX_PROBLEMS {
+            self.planned.push(Planned {
+                line,
+                key: link.key().to_string(),
+                url: link.url().to_string(),
+            });
+        }
+    }
+}
+
+// One link read from the input, not validated yet.
+#[derive(Debug)]
+pub struct Row {
+    line: usize,
+    url: request::Url,
+    created_at: Option<DateTime>,
+    // Owner recorded in the input, e.g. by an export; overrides the import's owner.
+    owner: Option<String>,
+    // Whether the key counts as custom. Migrated keys were mostly generated,
+    // so only rows that say so count against the custom key quota.
+    custom: bool,
+    // A click-limited link with no clicks left, as exports write them.
+    exhausted: bool,
+}
+
+impl Row {
+    // Fields arrive as a JSON object so CSV columns and JSON Lines share one
+    // path into `request::Url`. `key` is the link's key, `created_at` an
+    // RFC 3339 timestamp, `owner` the link's owner and `custom` whether its key
+    // was chosen; every other field is a `request::Url` field.
+    fn from_fields(line: usize, mut fields: Map<String, Value>) -> Result<Self, Problem> {
+        if let Some(key) = fields.remove("key") {
+            fields.insert(String::from("name"), key);
+        }
+        // Exports leave out password hashes. Importing such a link without
+        // its password would make it public, so it is refused instead.
+        if fields.remove("protected") == Some(Value::Bool(true)) {
+            return Err(Problem::new(
+                line,
+                fields.get("name").and_then(Value::as_str),
+                "password-protected links are exported without their password",
+            ));
+        }
+        let owner = match fields.remove("owner") {
+            Some(Value::String(owner)) if !owner.is_empty() => Some(owner),
+            Some(Value::String(_)) | None => None,
+            Some(_) => return Err(Problem::new(line, None, "invalid owner")),
+        };
+        // Used-up click-limited links are exported with `max_clicks: 0`,
+        // which create requests reject. It is taken out before validation
+        // and restored on the stored link, so backups restore as they were.
+        let exhausted = fields.get("max_clicks").and_then(Value::as_u64) == Some(0);
+        if exhausted {
+            fields.remove("max_clicks");
+        }
+        let custom = match fields.remove("custom") {
+            Some(Value::Bool(custom)) => custom,
+            None => false,
+            Some(_) => return Err(Problem::new(line, None, "invalid custom")),
+        };
+        let created_at = match fields.remove("created_at") {
+            Some(Value::String(at)) => Some(
+                DateTime::parse_rfc3339_str(&at)
+                    .map_err(|_| Problem::new(line, None, "invalid created_at"))?,
+            ),
+            Some(_) => return Err(Problem::new(line, None, "invalid created_at")),
+            None => None,
+        };
+        let url: request::Url = serde_json::from_value(Value::Object(fields))
+            .map_err(|err| Problem::new(line, None, err))?;
+        if url.name() == "-" {
+            return Err(Problem::new(line, None, "missing key"));
+        }
+        Ok(Row {
+            line,
+            url,
+            created_at,
+            owner,
+            custom,
+            exhausted,
+        })
+    }
+
+    // The stored link of a validated row; `owner` applies unless the row has one.
+    // Every row names its key, so `custom` comes from the row rather than
+    // from `to_model`, which treats named requests as custom keys.
+    fn link(&self, owner: &str) -> model::Url {
+        let owner = self.owner.as_deref().unwrap_or(owner);
+        let link = self
+            .url
+            .to_model(self.url.name(), owner)
+            .with_custom(self.custom);
+        let link = if self.exhausted {
+            link.with_max_clicks(Some(0))
+        } else {
+            link
+        };
+        match self.created_at {
+            Some(created_at) => link.with_created_at(created_at),
+            None => link,
+        }
+    }
+}
+
+/// Turns input lines into rows.
+/// CSV input starts with a header naming the columns, e.g.
+/// `key,url,created_at`; quoted fields must not span lines. Rows exported
+/// by other shorteners are renamed by the adapter for `source`.
+pub struct Parser {
+    format: Format,
+    source: Source,
+    header: Option<Vec<String>>,
+    line: usize,
+}
+
+impl Parser {
+    pub fn new(format: Format, source: Source) -> Self {
+        Parser {
+            format,
+            source,
+            header: None,
+            line: 0,
+        }
+    }
+
+    /// Parse the next line. `None` means the line holds no link: it is
+    /// blank or the CSV header.
+    pub fn parse(&mut self, text: &str) -> Option<Result<Row, Problem>> {
+        self.line += 1;
+        // Spreadsheet exports often start with a UTF-8 byte order mark.
+        let text = text
+            .trim_start_matches('\u{feff}')
+            .trim_end_matches(['\r', '\n']);
+        if text.trim().is_empty() {
+            return None;
+        }
+
+        let fields = match self.format {
+            Format::Jsonl => serde_json::from_str::<Map<String, Value>>(text)
+                .map_err(|err| Problem::new(self.line, None, err)),
+            Format::Csv => {
+                let record = match csv_record(text) {
+                    Ok(record) => record,
+                    Err(err) => return Some(Err(Problem::new(self.line, None, err))),
+                };
+                let Some(header) = &self.header else {
+                    self.header = Some(record.iter().map(|c| c.trim().to_string()).collect());
+                    return None;
+                };
+                // Empty cells count as missing, so optional columns may be left blank.
+                Ok(header
+                    .iter()
+                    .zip(record.iter())
+                    .filter(|(_, cell)| !cell.is_empty())
+                    .map(|(name, cell)| (name.clone(), csv_value(name, cell)))
+                    .collect())
+            }
+        };
+        Some(
+            fields.and_then(|fields| {
+                Row::from_fields(self.line, adapter::adapt(self.source, fields))
+            }),
+        )
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+fn csv_value(column: &str, cell: &str) -> Value {
+    // A list in one cell: `launch,q3` or `launch|q3`.
+    if column == "tags" {
+        return adapter::tags(Value::from(cell));
+    }
+    if TYPED_COLUMNS.contains(&column)
+        && let Ok(value) = serde_json::from_str(cell)
+    {
+        return value;
+    }
+    Value::String(cell.to_string())
+}
+
+fn csv_record(text: &str) -> Result<csv::StringRecord, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(text.as_bytes());
+    let mut record = csv::StringRecord::new();
+    reader.read_record(&mut record)?;
+    Ok(record)
+}
+
+/// Feeds rows into the store and keeps the tally.
+/// Importing is idempotent: keys that already point at the same URL are
+/// counted as existing, so an interrupted import can simply be run again,
+/// optionally skipping the lines the last report or log covered.
+pub struct Importer {
+    store: store::Url,
+    owner: String,
+    skip: usize,
+    dry_run: bool,
+    parser: Parser,
+    pending: Vec<(usize, model::Url)>,
+    report: Report,
+}
+
+impl Importer {
+    /// `owner` owns the imported links unless a row names its own.
+    pub fn new(store: store::Url, format: Format, owner: &str, skip: usize) -> Self {
+        Importer {
+            store,
+            owner: owner.to_string(),
+            skip,
+            dry_run: false,
+            parser: Parser::new(format, Source::Fesghel),
+            pending: Vec::new(),
+            report: Report::default(),
+        }
+    }
+
+    /// Read the export layout of another shortener.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.parser.source = source;
+        self
+    }
+
+    /// Check everything against the database but write nothing; the report
+    /// lists the links that would be created.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self.report.dry_run = dry_run;
+        self
+    }
+
+    /// Handle one input line. Only database failures are errors; bad rows
+    /// end up in the report.
+    pub async fn line(&mut self, text: &str) -> Result<(), store::Error> {
+        let parsed = self.parser.parse(text);
+        self.report.lines = self.parser.line();
+        // The CSV header is still parsed when skipping, since rows need it.
+        let Some(parsed) = parsed.filter(|_| self.parser.line() > self.skip) else {
+            return Ok(());
+        };
+
+        let row = match parsed {
+            Ok(row) => row,
+            Err(problem) => {
+                self.report.invalid += 1;
+                self.report.problem(problem);
+                return Ok(());
+            }
+        };
+        if let Err(err) = row.url.validate() {
+            self.report.invalid += 1;
+            self.report
+                .problem(Problem::new(row.line, Some(row.url.name()), err));
+            return Ok(());
+        }
+
+        self.pending.push((row.line, row.link(&self.owner)));
+        if self.pending.len() >= CHUNK {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Store what is left and hand out the report.
+    pub async fn finish(mut self) -> Result<Report, store::Error> {
+        self.flush().await?;
+        Ok(self.report)
+    }
+
+    async fn flush(&mut self) -> Result<(), store::Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        // `unzip` splits the pairs into two vectors.
+        let (lines, links): (Vec<usize>, Vec<model::Url>) = self.pending.drain(..).unzip();
+        if self.dry_run {
+            return self.plan(lines, links).await;
+        }
+        let results = self.store.store_many(&links).await?;
+
+        for ((line, link), result) in lines.into_iter().zip(&links).zip(results) {
+            match result {
+                Ok(()) => self.report.imported += 1,
+                Err(err) if err.is_duplicate_key() => match self.store.fetch(link.key()).await {
+                    Some(stored) if stored.url() == link.url() => self.report.existing += 1,
+                    _ => {
+                        self.report.conflicts += 1;
+                        self.report.problem(Problem::new(
+                            line,
+                            Some(link.key()),
+                            "key already exists with a different URL",
+                        ));
+                    }
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        log::info!(
+            "import stored everything through line {}",
+            self.report.lines
+        );
+        Ok(())
+    }
+
+    // Dry-run counterpart of `flush`: one lookup of the chunk's keys sorts
+    // its links into new, existing and conflicting ones.
+    async fn plan(
+        &mut self,
+        lines: Vec<usize>,
+        links: Vec<model::Url>,
+    ) -> Result<(), store::Error> {
+        let keys: Vec<&str> = links.iter().map(model::Url::key).collect();
+        let stored: HashMap<String, String> = self
+            .store
+            .find_keys(&keys)
+            .await?
+            .into_iter()
+            .map(|url| (url.key().to_string(), url.url().to_string()))
+            .collect();
+
+        for (line, link) in lines.into_iter().zip(&links) {
+            match stored.get(link.key()) {
+                None => {
+                    self.report.imported += 1;
+                    self.report.plan(line, link);
+                }
+                Some(url) if url == link.url() => self.report.existing += 1,
+                Some(_) => {
+                    self.report.conflicts += 1;
+                    self.report.problem(Problem::new(
+                        line,
+                        Some(link.key()),
+                        "key already exists with a different URL",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows are not comparable, so tests look at what they carry.
+    fn parse(parser: &mut Parser, text: &str) -> Option<Result<(String, String), Problem>> {
+        parser
+            .parse(text)
+            .map(|row| row.map(|row| (row.url.name().to_string(), row.url.url().to_string())))
+    }
+
+    #[test]
+    fn csv_uses_header() {
+        let mut p = Parser::new(Format::Csv, Source::Fesghel);
+        assert!(parse(&mut p, "url,key,created_at\n").is_none());
+        assert_eq!(
+            parse(&mut p, "https://example.com,abc,2024-01-02T03:04:05Z\r\n"),
+            Some(Ok(("abc".to_string(), "https://example.com".to_string())))
+        );
+        assert_eq!(p.line(), 2);
+    }
+
+    #[test]
+    fn csv_handles_quotes_and_blank_cells() {
+        let mut p = Parser::new(Format::Csv, Source::Fesghel);
+        p.parse("key,url,title,created_at");
+        let row = p
+            .parse(r#"abc,"https://example.com/?a=1,2","Hello, ""world""","#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.url.url(), "https://example.com/?a=1,2");
+        assert_eq!(row.url.title(), Some(r#"Hello, "world""#));
+        assert!(row.created_at.is_none());
+    }
+
+    #[test]
+    fn csv_reads_typed_columns() {
+        let mut p = Parser::new(Format::Csv, Source::Fesghel);
+        p.parse("key,url,redirect,max_clicks,forward_query,owner");
+        let row = p
+            .parse("abc,https://example.com,308,5,true,key:team")
+            .unwrap()
+            .unwrap();
+        assert!(row.url.validate().is_ok());
+        assert_eq!(row.url.redirect(), Some(model::Redirect::Permanent));
+        assert_eq!(row.url.max_clicks(), Some(5));
+        assert!(row.url.forward_query());
+    }
+
+    #[test]
+    fn csv_splits_tags() {
+        let mut p = Parser::new(Format::Csv, Source::Fesghel);
+        p.parse("key,url,tags");
+        let row = p
+            .parse(r#"abc,https://example.com,"launch, q3""#)
+            .unwrap()
+            .unwrap();
+        assert!(row.url.validate().is_ok());
+    }
+
+    #[test]
+    fn csv_from_other_shortener() {
+        let mut p = Parser::new(Format::Csv, Source::Yourls);
+        p.parse("keyword,url,title,timestamp,ip,clicks");
+        let row = p
+            .parse("abc,https://example.com,Example,2021-03-04 05:06:07,127.0.0.1,4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.url.name(), "abc");
+        assert_eq!(row.url.title(), Some("Example"));
+        assert_eq!(
+            row.created_at,
+            Some(DateTime::parse_rfc3339_str("2021-03-04T05:06:07Z").unwrap())
+        );
+    }
+
+    #[test]
+    fn jsonl_maps_key_and_created_at() {
+        let mut p = Parser::new(Format::Jsonl, Source::Fesghel);
+        let row = p
+            .parse(r#"{"key":"abc","url":"https://example.com","created_at":"2024-01-02T03:04:05Z","forward_query":true}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.url.name(), "abc");
+        assert!(row.url.forward_query());
+        assert_eq!(
+            row.created_at,
+            Some(DateTime::parse_rfc3339_str("2024-01-02T03:04:05Z").unwrap())
+        );
+    }
+
+    // What an export writes, the importer reads back.
+    #[test]
+    fn exports_round_trip() {
+        let at = |s| DateTime::parse_rfc3339_str(s).unwrap();
+        let original = model::Url::new("https://example.com/?a=1,2", "abc")
+            .with_owner("key:team")
+            .with_custom(true)
+            .with_created_at(at("2024-01-02T03:04:05Z"))
+            .with_redirect(Some(model::Redirect::try_from(308).unwrap()))
+            .with_window(None, Some(at("2100-01-01T00:00:00Z")))
+            .with_max_clicks(Some(10))
+            .with_tags(vec![String::from("launch")]);
+
+        for format in [Format::Jsonl, Format::Csv] {
+            let mut p = Parser::new(format, Source::Fesghel);
+            if format == Format::Csv {
+                p.parse(&<model::Url as crate::export::Record>::COLUMNS.join(","));
+            }
+            let line = crate::export::line(format, &original).unwrap();
+            let row = p
+                .parse(std::str::from_utf8(&line).unwrap())
+                .unwrap()
+                .unwrap();
+            assert!(row.url.validate().is_ok(), "{format:?}");
+
+            let imported = row.link("import");
+            assert_eq!(imported.key(), "abc");
+            assert_eq!(imported.url(), original.url());
+            assert_eq!(imported.owner(), Some("key:team"));
+            assert!(imported.custom());
+            assert_eq!(imported.created_at(), original.created_at());
+            assert_eq!(imported.redirect(), original.redirect());
+            assert_eq!(imported.not_after(), original.not_after());
+            assert_eq!(imported.max_clicks(), Some(10));
+            if format == Format::Jsonl {
+                assert_eq!(imported.tags(), ["launch"]);
+            }
+        }
+    }
+
+    #[test]
+    fn exhausted_links_round_trip() {
+        let original = model::Url::new("https://example.com", "once").with_max_clicks(Some(0));
+        for format in [Format::Jsonl, Format::Csv] {
+            let mut p = Parser::new(format, Source::Fesghel);
+            if format == Format::Csv {
+                p.parse(&<model::Url as crate::export::Record>::COLUMNS.join(","));
+            }
+            let line = crate::export::line(format, &original).unwrap();
+            let row = p
+                .parse(std::str::from_utf8(&line).unwrap())
+                .unwrap()
+                .unwrap();
+            assert!(row.url.validate().is_ok(), "{format:?}");
+            assert_eq!(row.link("import").max_clicks(), Some(0), "{format:?}");
+        }
+        // Create requests still need at least one click.
+        assert!(
+            request::Url::new("https://example.com")
+                .with_max_clicks(0)
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn protected_exports_are_refused() {
+        let link = model::Url::new("https://example.com", "abc").with_password(Some("s3cret"));
+        let line = crate::export::line(Format::Jsonl, &link).unwrap();
+        let mut p = Parser::new(Format::Jsonl, Source::Fesghel);
+        let problem = p
+            .parse(std::str::from_utf8(&line).unwrap())
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(problem.key.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn protected_csv_exports_are_refused() {
+        let link = model::Url::new("https://example.com", "abc").with_password(Some("s3cret"));
+        let mut p = Parser::new(Format::Csv, Source::Fesghel);
+        p.parse(&<model::Url as crate::export::Record>::COLUMNS.join(","));
+        let line = crate::export::line(Format::Csv, &link).unwrap();
+        assert!(
+            p.parse(std::str::from_utf8(&line).unwrap())
+                .unwrap()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rows_without_owner_get_the_imports() {
+        let mut p = Parser::new(Format::Jsonl, Source::Fesghel);
+        let row = p
+            .parse(r#"{"key":"abc","url":"https://example.com"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.link("team").owner(), Some("team"));
+    }
+
+    #[test]
+    fn keys_are_only_custom_when_the_row_says_so() {
+        let mut p = Parser::new(Format::Jsonl, Source::Fesghel);
+        let row = p
+            .parse(r#"{"key":"abc","url":"https://example.com"}"#)
+            .unwrap()
+            .unwrap();
+        assert!(!row.link("import").custom());
+        let row = p
+            .parse(r#"{"key":"docs","url":"https://example.com","custom":true}"#)
+            .unwrap()
+            .unwrap();
+        assert!(row.link("import").custom());
+        assert!(
+            p.parse(r#"{"key":"x","url":"https://example.com","custom":"yes"}"#)
+                .unwrap()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        let mut p = Parser::new(Format::Jsonl, Source::Fesghel);
+        assert!(p.parse("  \n").is_none());
+        assert_eq!(p.line(), 1);
+    }
+
+    #[test]
+    fn bad_rows_are_problems() {
+        let mut p = Parser::new(Format::Jsonl, Source::Fesghel);
+        assert_eq!(
+            p.parse(r#"{"url":"https://example.com"}"#)
+                .unwrap()
+                .unwrap_err(),
+            Problem::new(1, None, "missing key")
+        );
+        assert_eq!(
+            p.parse(r#"{"key":"a","url":"https://example.com","created_at":"yesterday"}"#)
+                .unwrap()
+                .unwrap_err(),
+            Problem::new(2, None, "invalid created_at")
+        );
+        assert!(p.parse("not json").unwrap().is_err());
+    }
+
+    #[test]
+    fn format_from_str() {
+        assert_eq!("csv".parse::<Format>(), Ok(Format::Csv));
+        assert_eq!("jsonl".parse::<Format>(), Ok(Format::Jsonl));
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn report_caps_problems() {
+        let mut report = Report::default();
+        for line in 0..MAX_PROBLEMS + 5 {
+            report.problem(Problem::new(line, None, "bad"));
+        }
+        assert_eq!(report.problems.len(), MAX_PROBLEMS);
+    }
+
+    #[test]
+    fn report_lists_planned_links() {
+        let mut report = Report::default();
+        report.plan(3, &model::Url::new("https://example.com", "abc"));
+        assert_eq!(
+            report.planned,
+            vec![Planned {
+                line: 3,
+                key: "abc".to_string(),
+                url: "https://example.com".to_string(),
+            }]
+        );
+    }
+}
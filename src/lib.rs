@@ -0,0 +1,27 @@
+// The library half of the crate: `main.rs` is a thin binary on top of it,
+// and other crates, such as the `fesghel-client` SDK in `client/`, share its
+// request and response types.
+// `pub mod` makes a module reachable from outside the crate.
+// Rust looks for `database.rs` or `database/mod.rs` for each declaration.
+pub mod cli;
+pub mod client;
+pub mod database;
+pub mod destination;
+pub mod export;
+pub mod geoip;
+pub mod handler;
+pub mod import;
+pub mod metrics;
+pub mod model;
+pub mod page;
+pub mod qr;
+pub mod ratelimit;
+pub mod request;
+pub mod response;
+pub mod service;
+pub mod setting;
+pub mod store;
+pub mod useragent;
+
+// The builder for embedding the shortener, e.g. `fesghel::Builder::new(..)`.
+pub use service::{Builder, Service};
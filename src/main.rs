@@ -1,18 +1,10 @@
-// `mod` declarations make submodules available to this crate.
-// Rust looks for `database.rs` or `database/mod.rs` for each declaration.
-mod database;
-mod handler;
-mod metrics;
-mod model;
-mod request;
-mod setting;
-mod store;
-
 // `use` brings items into scope, avoiding repetitive full paths.
-use actix_web::{App, HttpServer, web};
+use actix_web::{App, HttpServer};
 use actix_web_prom::PrometheusMetricsBuilder;
 
-use setting::Settings;
+// The modules live in the library crate (`lib.rs`), named after the package.
+use fesghel::setting::Settings;
+use fesghel::{cli, metrics};
 
 // Worker count as constant - can be made configurable via settings.
 const WORKERS: usize = 12;
@@ -25,11 +17,31 @@ async fn main() -> std::io::Result<()> {
     // Use for unrecoverable errors during initialization.
     simple_logger::init_with_level(log::Level::Info).expect("logger initiation failed");
 
+    // Commands other than `serve` run instead of the server, e.g. `fesghel list`.
+    // Arguments are parsed before the configuration is loaded, so `help` and
+    // usage errors work without one.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, rest) = match cli::parse(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{err}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if command == cli::Command::Help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
     let setting = Settings::new().expect("loading configuration failed");
 
-    // Register custom metrics before building the prometheus middleware.
-    metrics::register_metrics();
-    metrics::set_workers(WORKERS);
+    if command != cli::Command::Serve {
+        if let Err(err) = cli::run(command, &setting, rest).await {
+            eprintln!("fesghel {}: {err}", args[0]);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     // Build prometheus middleware with custom registry.
     // This exposes /metrics endpoint and tracks HTTP request metrics.
@@ -41,11 +53,17 @@ async fn main() -> std::io::Result<()> {
 
     // `.await` suspends execution until the Future completes.
     // This is non-blocking - other tasks can run while waiting.
-    let db = database::connect(setting.database()).await;
-
-    // Create store before server starts - `new()` is async to create indexes.
-    // Store is created once and cloned for each worker thread.
-    let store = store::Url::new(db).await;
+    // The builder connects to the database, creates the indexes and rejects
+    // a misconfiguration at startup rather than on first request.
+    let service = fesghel::Builder::connect(setting.database())
+        .await
+        .with_settings(&setting)
+        // `unwrap_or_else` with a diverging closure prints the error readably.
+        .unwrap_or_else(|err| panic!("invalid configuration: {err}"))
+        // Register custom metrics with the registry the middleware exports.
+        .with_metrics(&metrics::REGISTRY)
+        .build();
+    metrics::set_workers(WORKERS);
 
     log::info!(
         "starting server on {}:{} with {} workers",
@@ -54,22 +72,16 @@ async fn main() -> std::io::Result<()> {
         WORKERS
     );
 
-    // `move` keyword transfers ownership of captured variables (`store`) into the closure.
+    // `move` keyword transfers ownership of captured variables (`service`) into the closure.
     // Required here because the closure outlives the current function scope.
+    // The closure runs once per worker; each gets its own handler state, while
+    // the stores and rate limiters inside are shared.
     HttpServer::new(move || {
-        // `clone()` creates a deep copy. Required because each worker thread
-        // needs its own instance. `Url` implements `Clone` trait.
-        let store = store.clone();
-
         // Builder pattern: chain method calls that return `Self` for fluent API.
         App::new()
             // `.wrap()` adds middleware. Prometheus middleware tracks all requests.
             .wrap(prometheus.clone())
-            .service(crate::handler::url::register(
-                crate::handler::url::State::new(store),
-                web::scope("/api"),
-            ))
-            .service(crate::handler::healthz::register(web::scope("")))
+            .configure(|cfg| service.configure(cfg))
     })
     .workers(WORKERS)
     // `format!` macro creates a formatted String, similar to printf.
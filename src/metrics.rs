@@ -3,6 +3,7 @@
 
 use std::sync::LazyLock;
 
+use prometheus::core::Collector;
 use prometheus::{Counter, Gauge, Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
 
 // `LazyLock` creates static variables that are initialized on first access.
@@ -36,7 +37,23 @@ pub static URLS_CREATED: LazyLock<Counter> = LazyLock::new(|| {
 pub static ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
     IntCounterVec::new(
         Opts::new("fesghel_errors_total", "Total number of errors by type"),
-        &["type"], // Label name - values: "duplicate_key", "database", "validation"
+        // Label name - values: "duplicate_key", "database", "validation",
+        // "rate_limited", "quota_exceeded", "wrong_password", "unauthorized"
+        &["type"],
+    )
+    .expect("metric can be created")
+});
+
+// Redirects served per split-link target index. Labelled by index only: a
+// per-link label would grow with every split link ever created. Per-link
+// numbers come from the stored click events.
+pub static VARIANT_REDIRECTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "fesghel_variant_redirects_total",
+            "Total number of redirects per split link target",
+        ),
+        &["variant"],
     )
     .expect("metric can be created")
 });
@@ -93,40 +110,28 @@ pub static DB_WRITE_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
     .expect("metric can be created")
 });
 
-/// Register all custom metrics with the registry.
-/// Called once at application startup before creating the prometheus middleware.
-pub fn register_metrics() {
-    REGISTRY
-        .register(Box::new(WORKERS.clone()))
-        .expect("WORKERS metric registered");
-
-    REGISTRY
-        .register(Box::new(URLS_CREATED.clone()))
-        .expect("URLS_CREATED metric registered");
-
-    REGISTRY
-        .register(Box::new(ERRORS.clone()))
-        .expect("ERRORS metric registered");
-
-    REGISTRY
-        .register(Box::new(APP_INFO.clone()))
-        .expect("APP_INFO metric registered");
-
-    REGISTRY
-        .register(Box::new(DB_READS.clone()))
-        .expect("DB_READS metric registered");
-
-    REGISTRY
-        .register(Box::new(DB_WRITES.clone()))
-        .expect("DB_WRITES metric registered");
-
-    REGISTRY
-        .register(Box::new(DB_READ_DURATION.clone()))
-        .expect("DB_READ_DURATION metric registered");
-
-    REGISTRY
-        .register(Box::new(DB_WRITE_DURATION.clone()))
-        .expect("DB_WRITE_DURATION metric registered");
+/// Register all custom metrics with `registry`, usually `REGISTRY`.
+/// Called at startup before creating the prometheus middleware. Embedding
+/// applications may pass their own registry; registering twice is harmless.
+pub fn register_metrics(registry: &Registry) {
+    // `Box<dyn Collector>` lets metrics of different types share one array.
+    let collectors: [Box<dyn Collector>; 9] = [
+        Box::new(WORKERS.clone()),
+        Box::new(URLS_CREATED.clone()),
+        Box::new(ERRORS.clone()),
+        Box::new(VARIANT_REDIRECTS.clone()),
+        Box::new(APP_INFO.clone()),
+        Box::new(DB_READS.clone()),
+        Box::new(DB_WRITES.clone()),
+        Box::new(DB_READ_DURATION.clone()),
+        Box::new(DB_WRITE_DURATION.clone()),
+    ];
+    for collector in collectors {
+        match registry.register(collector) {
+            Ok(()) | Err(prometheus::Error::AlreadyReg) => {}
+            Err(err) => panic!("registering metric failed: {err}"),
+        }
+    }
 
     // Set app info to 1 (presence indicator with version label).
     APP_INFO.set(1.0);
@@ -145,11 +150,20 @@ pub fn inc_urls_created() {
 }
 
 /// Increment error counter by type.
-/// Error types: "duplicate_key", "database", "validation"
+/// Error types: "duplicate_key", "database", "validation", "rate_limited", "quota_exceeded",
+/// "wrong_password", "unauthorized"
 pub fn inc_error(error_type: &str) {
     ERRORS.with_label_values(&[error_type]).inc();
 }
 
+/// Increment the redirect counter of one split link target.
+/// `variant` is the index of the target in the link's target list.
+pub fn inc_variant_redirect(variant: u32) {
+    VARIANT_REDIRECTS
+        .with_label_values(&[&variant.to_string()])
+        .inc();
+}
+
 /// Record a database read operation with its duration.
 /// Duration should be in seconds (use `Instant::elapsed().as_secs_f64()`).
 pub fn observe_db_read(duration_secs: f64) {
@@ -174,6 +188,19 @@ mod tests {
         assert_eq!(WORKERS.get() as usize, 12);
     }
 
+    #[test]
+    fn registering_twice_is_harmless() {
+        let registry = Registry::new();
+        register_metrics(&registry);
+        register_metrics(&registry);
+        assert!(
+            registry
+                .gather()
+                .iter()
+                .any(|family| family.name() == "fesghel_app_info")
+        );
+    }
+
     #[test]
     fn urls_counter_increments() {
         let before = URLS_CREATED.get() as u64;
@@ -188,6 +215,16 @@ mod tests {
         assert_eq!(ERRORS.with_label_values(&["test_error"]).get(), before + 1);
     }
 
+    #[test]
+    fn variant_redirect_counter_increments() {
+        let before = VARIANT_REDIRECTS.with_label_values(&["1"]).get();
+        inc_variant_redirect(1);
+        assert_eq!(
+            VARIANT_REDIRECTS.with_label_values(&["1"]).get(),
+            before + 1
+        );
+    }
+
     #[test]
     fn db_read_increments_counter_and_histogram() {
         let before = DB_READS.get() as u64;
@@ -0,0 +1,72 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+// One successful redirect, stored for analytics.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Click {
+    key: String,
+    at: DateTime,
+    // Index of the split-link target that was served, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variant: Option<u32>,
+    // Visitor country from the GeoIP database (ISO 3166-1 alpha-2).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    country: Option<String>,
+}
+
+impl Click {
+    pub fn new(key: &str, variant: Option<u32>) -> Self {
+        Click {
+            key: String::from(key),
+            at: DateTime::now(),
+            variant,
+            country: None,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    pub fn at(&self) -> DateTime {
+        self.at
+    }
+
+    pub fn variant(&self) -> Option<u32> {
+        self.variant
+    }
+
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+
+    pub fn with_country(mut self, country: Option<String>) -> Self {
+        self.country = country;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_is_omitted_when_absent() {
+        let json = serde_json::to_string(&Click::new("k", None)).unwrap();
+        assert!(json.contains(r#""key":"k""#));
+        assert!(!json.contains("variant"));
+    }
+
+    #[test]
+    fn country_is_recorded() {
+        let click = Click::new("k", None).with_country(Some("DE".to_string()));
+        let json = serde_json::to_string(&click).unwrap();
+        assert!(json.contains(r#""country":"DE""#));
+    }
+
+    #[test]
+    fn variant_is_recorded() {
+        let json = serde_json::to_string(&Click::new("k", Some(1))).unwrap();
+        assert!(json.contains(r#""variant":1"#));
+    }
+}
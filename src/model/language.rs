@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Destination for visitors preferring one language, e.g. "de" or "pt-BR".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Localized {
+    language: String,
+    url: String,
+}
+
+impl Localized {
+    pub fn language(&self) -> &str {
+        self.language.as_str()
+    }
+
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+}
+
+/// Parse an `Accept-Language` header into language ranges ordered by preference.
+/// Ranges with `q=0` are explicitly unwanted and dropped; malformed quality
+/// values count as 1 like a missing one.
+pub fn preferences(header: &str) -> Vec<String> {
+    let mut ranges: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            // `split(';')` separates the range from its parameters, e.g. "de;q=0.8".
+            let mut pieces = part.split(';');
+            let range = pieces.next()?.trim();
+            if range.is_empty() {
+                return None;
+            }
+            let q = pieces
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((range.to_ascii_lowercase(), q))
+        })
+        .filter(|(_, q)| *q > 0.0)
+        .collect();
+
+    // Stable sort keeps header order among equal weights.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(range, _)| range).collect()
+}
+
+/// Pick the destination best matching the visitor's preferences.
+/// For each preferred range, in order: an exact match, then a match on the
+/// range's primary subtag ("en-US" accepts "en"), then any regional variant of
+/// it ("en" accepts "en-GB"). The wildcard `*` is left to the default destination.
+pub fn negotiate<'a>(header: &str, localized: &'a [Localized]) -> Option<&'a Localized> {
+    for range in preferences(header) {
+        if range == "*" {
+            continue;
+        }
+        let primary = range.split('-').next().unwrap_or(&range);
+
+        let exact = localized
+            .iter()
+            .find(|l| l.language.eq_ignore_ascii_case(&range));
+        let truncated = || {
+            localized
+                .iter()
+                .find(|l| l.language.eq_ignore_ascii_case(primary))
+        };
+        let regional = || {
+            localized.iter().find(|l| {
+                l.language
+                    .split('-')
+                    .next()
+                    .is_some_and(|p| p.eq_ignore_ascii_case(primary))
+            })
+        };
+
+        if let Some(found) = exact.or_else(truncated).or_else(regional) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn localized(json: &str) -> Vec<Localized> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn preferences_ordered_by_quality() {
+        assert_eq!(
+            preferences("fr;q=0.5, de-DE, en;q=0.8, *;q=0.1"),
+            vec!["de-de", "en", "fr", "*"]
+        );
+    }
+
+    #[test]
+    fn preferences_drop_unwanted_and_empty() {
+        assert_eq!(preferences("de;q=0, en,,"), vec!["en"]);
+        assert!(preferences("").is_empty());
+    }
+
+    #[test]
+    fn malformed_quality_counts_as_one() {
+        assert_eq!(preferences("fr;q=0.9, de;q=abc"), vec!["de", "fr"]);
+    }
+
+    #[test]
+    fn negotiate_prefers_exact_match() {
+        let l = localized(
+            r#"[{"language":"pt","url":"https://pt"},{"language":"pt-BR","url":"https://br"}]"#,
+        );
+        assert_eq!(negotiate("pt-BR", &l).unwrap().url(), "https://br");
+        assert_eq!(negotiate("pt-PT", &l).unwrap().url(), "https://pt");
+    }
+
+    #[test]
+    fn negotiate_accepts_regional_variant() {
+        let l = localized(r#"[{"language":"en-GB","url":"https://uk"}]"#);
+        assert_eq!(negotiate("en", &l).unwrap().url(), "https://uk");
+    }
+
+    #[test]
+    fn negotiate_follows_preference_order() {
+        let l = localized(
+            r#"[{"language":"en","url":"https://en"},{"language":"de","url":"https://de"}]"#,
+        );
+        assert_eq!(
+            negotiate("fr, de;q=0.9, en;q=0.8", &l).unwrap().url(),
+            "https://de"
+        );
+    }
+
+    #[test]
+    fn negotiate_without_match() {
+        let l = localized(r#"[{"language":"de","url":"https://de"}]"#);
+        assert!(negotiate("fr, *;q=0.5", &l).is_none());
+        assert!(negotiate("", &l).is_none());
+    }
+}
@@ -1,3 +1,17 @@
+mod click;
+mod language;
+mod password;
+mod redirect;
+mod rule;
+mod target;
 mod url;
+mod utm;
 
+pub use click::*;
+pub use language::*;
+pub use password::*;
+pub use redirect::*;
+pub use rule::*;
+pub use target::*;
 pub use url::*;
+pub use utm::*;
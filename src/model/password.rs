@@ -0,0 +1,157 @@
+use argon2::{Algorithm, Argon2, Version};
+use rand::{RngExt, rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+// Random bytes mixed into every hash so equal passwords hash differently.
+const SALT_LENGTH: usize = 16;
+// Length of the derived key.
+const HASH_LENGTH: usize = 32;
+
+// Argon2id work factor: memory in KiB, passes and lanes. These are the
+// `argon2` crate's defaults, as recommended by OWASP. Each hash records the
+// parameters it was made with, so they can be raised without breaking old links.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cost {
+    m: u32,
+    t: u32,
+    p: u32,
+}
+
+const COST: Cost = Cost {
+    m: argon2::Params::DEFAULT_M_COST,
+    t: argon2::Params::DEFAULT_T_COST,
+    p: argon2::Params::DEFAULT_P_COST,
+};
+
+// Argon2id hash of a link's shared secret. A memory-hard KDF keeps offline
+// guessing slow even on GPUs. Salt and hash are hex encoded so they read
+// naturally in MongoDB. Links protected before Argon2id was introduced have
+// no `argon2` cost and a salted SHA-256 hash; they keep verifying that way.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Password {
+    salt: String,
+    hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    argon2: Option<Cost>,
+}
+
+impl Password {
+    pub fn new(plain: &str) -> Self {
+        let salt: [u8; SALT_LENGTH] = rng().random();
+        // The parameters are constants known to be valid.
+        let hash = derive(COST, &salt, plain).expect("Argon2 parameters are valid");
+        Password {
+            salt: hex::encode(salt),
+            hash: hex::encode(hash),
+            argon2: Some(COST),
+        }
+    }
+
+    /// Check `plain` against the stored hash.
+    /// The comparison takes the same time wherever the first differing byte is,
+    /// so response timing does not leak how close a guess was.
+    /// Argon2id is slow by design; call it off the async worker threads.
+    pub fn verify(&self, plain: &str) -> bool {
+        let (Ok(salt), Ok(hash)) = (hex::decode(&self.salt), hex::decode(&self.hash)) else {
+            return false;
+        };
+        let computed = match self.argon2 {
+            Some(cost) => match derive(cost, &salt, plain) {
+                Some(computed) => computed,
+                None => return false,
+            },
+            None => legacy_digest(&salt, plain),
+        };
+        computed.ct_eq(&hash).into()
+    }
+}
+
+// Hand-written `Debug` so hashes never end up in logs.
+impl std::fmt::Debug for Password {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Password(..)")
+    }
+}
+
+// `None` when stored parameters are out of Argon2's range, e.g. tampered with.
+fn derive(cost: Cost, salt: &[u8], plain: &str) -> Option<Vec<u8>> {
+    let params = argon2::Params::new(cost.m, cost.t, cost.p, Some(HASH_LENGTH)).ok()?;
+    let mut hash = vec![0; HASH_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(plain.as_bytes(), salt, &mut hash)
+        .ok()?;
+    Some(hash)
+}
+
+// Salted SHA-256, as stored for links protected before Argon2id.
+fn legacy_digest(salt: &[u8], plain: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(plain.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_correct_password() {
+        let p = Password::new("s3cret");
+        assert!(p.verify("s3cret"));
+        assert!(!p.verify("s3cret "));
+        assert!(!p.verify(""));
+    }
+
+    #[test]
+    fn salts_are_random() {
+        let a = Password::new("same");
+        let b = Password::new("same");
+        assert_ne!(a.salt, b.salt);
+        assert_ne!(a.hash, b.hash);
+    }
+
+    #[test]
+    fn corrupted_hash_never_verifies() {
+        let mut p = Password::new("x");
+        p.hash = String::from("not hex");
+        assert!(!p.verify("x"));
+    }
+
+    #[test]
+    fn debug_hides_hash() {
+        let p = Password::new("x");
+        assert_eq!(format!("{:?}", p), "Password(..)");
+    }
+
+    #[test]
+    fn new_hashes_use_argon2id() {
+        let p = Password::new("s3cret");
+        assert_eq!(p.argon2, Some(COST));
+        assert_ne!(
+            hex::decode(&p.hash).unwrap(),
+            legacy_digest(&hex::decode(&p.salt).unwrap(), "s3cret")
+        );
+    }
+
+    #[test]
+    fn legacy_sha256_hashes_still_verify() {
+        let salt = [7u8; SALT_LENGTH];
+        let p: Password = serde_json::from_value(serde_json::json!({
+            "salt": hex::encode(salt),
+            "hash": hex::encode(legacy_digest(&salt, "old")),
+        }))
+        .unwrap();
+        assert!(p.verify("old"));
+        assert!(!p.verify("new"));
+    }
+
+    #[test]
+    fn invalid_cost_never_verifies() {
+        let mut p = Password::new("x");
+        p.argon2 = Some(Cost { m: 0, t: 0, p: 0 });
+        assert!(!p.verify("x"));
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+// HTTP redirect flavours a link can answer with.
+// `try_from`/`into` make serde store the plain status code (e.g. `301`)
+// instead of the variant name, which is what API users think in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum Redirect {
+    // 301 - permanent, clients may rewrite the method to GET.
+    MovedPermanently,
+    // 302 - temporary, clients may rewrite the method to GET.
+    Found,
+    // 307 - temporary, method and body are preserved.
+    Temporary,
+    // 308 - permanent, method and body are preserved.
+    Permanent,
+}
+
+impl Redirect {
+    pub fn status(self) -> u16 {
+        match self {
+            Redirect::MovedPermanently => 301,
+            Redirect::Found => 302,
+            Redirect::Temporary => 307,
+            Redirect::Permanent => 308,
+        }
+    }
+
+    // Permanent redirects may be cached by browsers and CDNs.
+    pub fn is_permanent(self) -> bool {
+        matches!(self, Redirect::MovedPermanently | Redirect::Permanent)
+    }
+}
+
+// `TryFrom` is the fallible counterpart of `From`.
+// The error carries the rejected status code; serde only needs it to be `Display`.
+impl TryFrom<u16> for Redirect {
+    type Error = u16;
+
+    fn try_from(status: u16) -> Result<Self, Self::Error> {
+        match status {
+            301 => Ok(Redirect::MovedPermanently),
+            302 => Ok(Redirect::Found),
+            307 => Ok(Redirect::Temporary),
+            308 => Ok(Redirect::Permanent),
+            other => Err(other),
+        }
+    }
+}
+
+impl From<Redirect> for u16 {
+    fn from(redirect: Redirect) -> Self {
+        redirect.status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_roundtrip() {
+        for status in [301, 302, 307, 308] {
+            assert_eq!(Redirect::try_from(status).unwrap().status(), status);
+        }
+    }
+
+    #[test]
+    fn rejects_non_redirect_status() {
+        assert_eq!(Redirect::try_from(200), Err(200));
+        assert_eq!(Redirect::try_from(303), Err(303));
+    }
+
+    #[test]
+    fn permanence() {
+        assert!(Redirect::MovedPermanently.is_permanent());
+        assert!(Redirect::Permanent.is_permanent());
+        assert!(!Redirect::Found.is_permanent());
+        assert!(!Redirect::Temporary.is_permanent());
+    }
+
+    #[test]
+    fn serializes_as_status_code() {
+        assert_eq!(serde_json::to_string(&Redirect::Permanent).unwrap(), "308");
+        let r: Redirect = serde_json::from_str("301").unwrap();
+        assert_eq!(r, Redirect::MovedPermanently);
+        assert!(serde_json::from_str::<Redirect>("200").is_err());
+    }
+}
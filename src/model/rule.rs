@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// `rename_all` maps variants to lowercase JSON/BSON strings, e.g. `Os::Ios` <-> "ios".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Os {
+    Ios,
+    Android,
+    Windows,
+    Macos,
+    Linux,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    Mobile,
+    Tablet,
+    Desktop,
+}
+
+// What is known about the visitor when rules are evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visitor {
+    pub os: Os,
+    pub device: Device,
+    pub bot: bool,
+    // ISO 3166-1 alpha-2 code, uppercase. `None` when geo lookup is off or failed.
+    pub country: Option<String>,
+}
+
+impl Visitor {
+    pub fn with_country(mut self, country: Option<String>) -> Self {
+        self.country = country;
+        self
+    }
+}
+
+// Conditional destination of a link. Every condition that is set must match;
+// a rule without conditions matches everyone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Rule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    os: Option<Os>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device: Option<Device>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bot: Option<bool>,
+    // Matches visitors located in any of these countries (ISO 3166-1 alpha-2).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    countries: Vec<String>,
+    url: String,
+}
+
+impl Rule {
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    pub fn countries(&self) -> &[String] {
+        &self.countries
+    }
+
+    pub fn matches(&self, visitor: &Visitor) -> bool {
+        // `is_none_or` treats an unset condition as satisfied.
+        self.os.is_none_or(|os| os == visitor.os)
+            && self.device.is_none_or(|device| device == visitor.device)
+            && self.bot.is_none_or(|bot| bot == visitor.bot)
+            && (self.countries.is_empty()
+                || visitor
+                    .country
+                    .as_deref()
+                    .is_some_and(|c| self.countries.iter().any(|r| r.eq_ignore_ascii_case(c))))
+    }
+}
+
+/// First rule matching the visitor, in the order the rules were given.
+pub fn first_match<'a>(rules: &'a [Rule], visitor: &Visitor) -> Option<&'a Rule> {
+    rules.iter().find(|rule| rule.matches(visitor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(json: &str) -> Vec<Rule> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    const IPHONE: Visitor = Visitor {
+        os: Os::Ios,
+        device: Device::Mobile,
+        bot: false,
+        country: None,
+    };
+
+    const DESKTOP: Visitor = Visitor {
+        os: Os::Windows,
+        device: Device::Desktop,
+        bot: false,
+        country: None,
+    };
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = rules(
+            r#"[
+                {"os":"ios","url":"https://apps.apple.com/app"},
+                {"device":"mobile","url":"https://m.example.com"}
+            ]"#,
+        );
+        assert_eq!(
+            first_match(&rules, &IPHONE).unwrap().url(),
+            "https://apps.apple.com/app"
+        );
+        assert!(first_match(&rules, &DESKTOP).is_none());
+    }
+
+    #[test]
+    fn all_conditions_must_match() {
+        let rules = rules(r#"[{"os":"windows","bot":true,"url":"https://bots"}]"#);
+        assert!(first_match(&rules, &DESKTOP).is_none());
+        let bot = Visitor {
+            bot: true,
+            ..DESKTOP
+        };
+        assert!(first_match(&rules, &bot).is_some());
+    }
+
+    #[test]
+    fn country_rules() {
+        let rules = rules(
+            r#"[
+                {"countries":["DE","at"],"url":"https://example.de"},
+                {"url":"https://example.com"}
+            ]"#,
+        );
+        let austria = DESKTOP.with_country(Some("AT".to_string()));
+        assert_eq!(
+            first_match(&rules, &austria).unwrap().url(),
+            "https://example.de"
+        );
+        let france = DESKTOP.with_country(Some("FR".to_string()));
+        assert_eq!(
+            first_match(&rules, &france).unwrap().url(),
+            "https://example.com"
+        );
+        // Unknown location never matches a country condition.
+        assert_eq!(
+            first_match(&rules, &DESKTOP).unwrap().url(),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn unconditional_rule_matches_everyone() {
+        let rules = rules(r#"[{"url":"https://all"}]"#);
+        assert!(first_match(&rules, &IPHONE).is_some());
+        assert!(first_match(&rules, &DESKTOP).is_some());
+    }
+
+    #[test]
+    fn unset_conditions_are_not_serialized() {
+        let rules = rules(r#"[{"device":"tablet","url":"https://t"}]"#);
+        assert_eq!(
+            serde_json::to_string(&rules[0]).unwrap(),
+            r#"{"device":"tablet","url":"https://t"}"#
+        );
+    }
+}
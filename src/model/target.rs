@@ -0,0 +1,78 @@
+use rand::RngExt;
+use rand::distr::weighted::WeightedIndex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// One destination of a split (A/B) link. Traffic is routed to each target in
+// proportion to its weight, so weights 70 and 30 split traffic 70/30.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Target {
+    url: String,
+    weight: u32,
+}
+
+impl Target {
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+}
+
+/// Pick a target index at random according to the weights.
+/// Returns `None` when there are no targets or every weight is zero.
+// Generic over `RngExt` so tests can pass a seeded generator.
+pub fn choose<R: RngExt>(targets: &[Target], rng: &mut R) -> Option<usize> {
+    let weights = WeightedIndex::new(targets.iter().map(Target::weight)).ok()?;
+    Some(rng.sample(&weights))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn target(url: &str, weight: u32) -> Target {
+        Target {
+            url: url.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn empty_targets_choose_nothing() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(choose(&[], &mut rng), None);
+        assert_eq!(choose(&[target("https://a", 0)], &mut rng), None);
+    }
+
+    #[test]
+    fn zero_weight_is_never_chosen() {
+        let targets = [target("https://a", 0), target("https://b", 1)];
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            assert_eq!(choose(&targets, &mut rng), Some(1));
+        }
+    }
+
+    #[test]
+    fn follows_weights() {
+        let targets = [target("https://a", 70), target("https://b", 30)];
+        let mut rng = StdRng::seed_from_u64(42);
+        let a = (0..10_000)
+            .filter(|_| choose(&targets, &mut rng) == Some(0))
+            .count();
+        // 70% with a generous margin for randomness.
+        assert!((6_500..7_500).contains(&a), "got {a}");
+    }
+
+    #[test]
+    fn deserializes_from_json() {
+        let t: Target = serde_json::from_str(r#"{"url":"https://a","weight":3}"#).unwrap();
+        assert_eq!(t.url(), "https://a");
+        assert_eq!(t.weight(), 3);
+    }
+}
@@ -1,8 +1,11 @@
 // Serde is Rust's serialization framework.
 // `Serialize` converts Rust types to formats like JSON/BSON.
 // `Deserialize` converts JSON/BSON back to Rust types.
+use mongodb::bson::DateTime;
 use serde::{Deserialize, Serialize};
 
+use super::{Localized, Password, Redirect, Rule, Target, Utm};
+
 // Multiple derives can be combined in one attribute.
 // `Debug` - enables `{:?}` formatting for debugging
 // `Serialize` - enables conversion TO JSON/BSON (for responses)
@@ -13,6 +16,77 @@ pub struct Url {
     // Fields are private by default - only accessible within this module.
     url: String,
     key: String,
+    // Identity of the creator (API key or IP address) used for quotas.
+    // `skip_serializing_if` keeps documents without an owner compact, and
+    // `default` lets older documents without the field deserialize.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    // Whether the key was chosen by the client instead of generated.
+    #[serde(default)]
+    custom: bool,
+    // Creation time. Missing on links stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime>,
+    // Per-link redirect status. `None` falls back to the server default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    redirect: Option<Redirect>,
+    // Opt-in passthrough of the visitor's query string and extra path segments.
+    #[serde(default)]
+    forward_query: bool,
+    #[serde(default)]
+    forward_path: bool,
+    // Serve an interstitial page showing the destination instead of redirecting.
+    #[serde(default)]
+    preview: bool,
+    // Open Graph metadata shown to link-unfurling bots of chat apps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    // Free-form labels, e.g. carried over from another shortener.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    // Campaign parameters, kept apart from `url` for reporting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    utm: Option<Utm>,
+    // Shared secret visitors must enter before being redirected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<Password>,
+    // Remaining redirects for click-limited links; decremented by the store.
+    // `i64` because BSON has no unsigned integers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<i64>,
+    // Activation window. Stored as BSON dates so MongoDB can query them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not_before: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not_after: Option<DateTime>,
+    // Where visitors go outside the activation window instead of an error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fallback: Option<String>,
+    // Weighted destinations for split (A/B) tests. When present they are
+    // used instead of `url`, which stays as the canonical destination.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    targets: Vec<Target>,
+    // Keep each visitor on the target they were first sent to.
+    #[serde(default)]
+    sticky: bool,
+    // Visitor-dependent destinations, evaluated in order before `targets`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rules: Vec<Rule>,
+    // Localized destinations negotiated from `Accept-Language`, after `rules`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    languages: Vec<Localized>,
+}
+
+// Position of an instant relative to a link's activation window.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Window {
+    Pending,
+    Active,
+    Expired,
 }
 
 impl Url {
@@ -27,6 +101,114 @@ impl Url {
         self.url.as_str()
     }
 
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    // Whether the key counts against the owner's custom key quota.
+    pub fn custom(&self) -> bool {
+        self.custom
+    }
+
+    pub fn created_at(&self) -> Option<DateTime> {
+        self.created_at
+    }
+
+    // `Redirect` is `Copy`, so returning the Option by value is cheap.
+    pub fn redirect(&self) -> Option<Redirect> {
+        self.redirect
+    }
+
+    pub fn password(&self) -> Option<&Password> {
+        self.password.as_ref()
+    }
+
+    pub fn max_clicks(&self) -> Option<i64> {
+        self.max_clicks
+    }
+
+    pub fn not_before(&self) -> Option<DateTime> {
+        self.not_before
+    }
+
+    pub fn not_after(&self) -> Option<DateTime> {
+        self.not_after
+    }
+
+    pub fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    // Both bounds are inclusive.
+    pub fn window(&self, now: DateTime) -> Window {
+        if self.not_before.is_some_and(|t| now < t) {
+            Window::Pending
+        } else if self.not_after.is_some_and(|t| now > t) {
+            Window::Expired
+        } else {
+            Window::Active
+        }
+    }
+
+    // `&[Target]` is a slice - a borrowed view into the Vec.
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
+    }
+
+    pub fn sticky(&self) -> bool {
+        self.sticky
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn languages(&self) -> &[Localized] {
+        &self.languages
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_open_graph(&self) -> bool {
+        self.title.is_some() || self.description.is_some() || self.image.is_some()
+    }
+
+    // Dynamic links may send two visitors to different places, so their
+    // redirects must not be shared through caches. Links with Open Graph
+    // metadata answer bots with a page instead of the redirect.
+    pub fn is_dynamic(&self) -> bool {
+        !self.targets.is_empty()
+            || !self.rules.is_empty()
+            || !self.languages.is_empty()
+            || self.has_open_graph()
+    }
+
+    pub fn forward_query(&self) -> bool {
+        self.forward_query
+    }
+
+    pub fn forward_path(&self) -> bool {
+        self.forward_path
+    }
+
+    pub fn preview(&self) -> bool {
+        self.preview
+    }
+
     // Constructor pattern: `new()` is conventional name for creating instances.
     // Takes `&str` (borrowed) and converts to owned `String` internally.
     pub fn new(url: &str, key: &str) -> Self {
@@ -35,8 +217,125 @@ impl Url {
             // This allocates memory and copies the string data.
             url: String::from(url),
             key: String::from(key),
+            owner: None,
+            custom: false,
+            redirect: None,
+            forward_query: false,
+            forward_path: false,
+            preview: false,
+            created_at: None,
+            title: None,
+            description: None,
+            image: None,
+            tags: Vec::new(),
+            utm: None,
+            password: None,
+            max_clicks: None,
+            not_before: None,
+            not_after: None,
+            fallback: None,
+            targets: Vec::new(),
+            sticky: false,
+            rules: Vec::new(),
+            languages: Vec::new(),
         }
     }
+
+    // Builder-style setters consume and return `self` so they chain after `new()`.
+    pub fn with_owner(mut self, owner: &str) -> Self {
+        self.owner = Some(String::from(owner));
+        self
+    }
+
+    pub fn with_custom(mut self, custom: bool) -> Self {
+        self.custom = custom;
+        self
+    }
+
+    pub fn with_redirect(mut self, redirect: Option<Redirect>) -> Self {
+        self.redirect = redirect;
+        self
+    }
+
+    pub fn with_forwarding(mut self, query: bool, path: bool) -> Self {
+        self.forward_query = query;
+        self.forward_path = path;
+        self
+    }
+
+    pub fn with_created_at(mut self, created_at: DateTime) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    pub fn with_preview(mut self, preview: bool) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    pub fn with_utm(mut self, utm: Option<Utm>) -> Self {
+        self.utm = utm;
+        self
+    }
+
+    pub fn with_open_graph(
+        mut self,
+        title: Option<&str>,
+        description: Option<&str>,
+        image: Option<&str>,
+    ) -> Self {
+        self.title = title.map(String::from);
+        self.description = description.map(String::from);
+        self.image = image.map(String::from);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_max_clicks(mut self, max_clicks: Option<u32>) -> Self {
+        self.max_clicks = max_clicks.map(i64::from);
+        self
+    }
+
+    pub fn with_window(
+        mut self,
+        not_before: Option<DateTime>,
+        not_after: Option<DateTime>,
+    ) -> Self {
+        self.not_before = not_before;
+        self.not_after = not_after;
+        self
+    }
+
+    pub fn with_fallback(mut self, fallback: Option<&str>) -> Self {
+        self.fallback = fallback.map(String::from);
+        self
+    }
+
+    pub fn with_targets(mut self, targets: Vec<Target>, sticky: bool) -> Self {
+        self.targets = targets;
+        self.sticky = sticky;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_languages(mut self, languages: Vec<Localized>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    // Takes the plain secret and stores only its salted hash.
+    pub fn with_password(mut self, plain: Option<&str>) -> Self {
+        self.password = plain.map(Password::new);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -91,6 +390,134 @@ mod tests {
         assert_eq!(original.key(), restored.key());
     }
 
+    #[test]
+    fn deserialize_without_owner_defaults() {
+        let json = r#"{"url":"https://example.com","key":"old"}"#;
+        let url: Url = serde_json::from_str(json).unwrap();
+        assert_eq!(url.owner, None);
+        assert!(!url.custom);
+    }
+
+    #[test]
+    fn owner_is_omitted_when_absent() {
+        let json = serde_json::to_string(&Url::new("https://example.com", "k")).unwrap();
+        assert!(!json.contains("owner"));
+    }
+
+    #[test]
+    fn with_owner_and_custom() {
+        let url = Url::new("https://example.com", "k")
+            .with_owner("ip:10.0.0.1")
+            .with_custom(true);
+        let json = serde_json::to_string(&url).unwrap();
+        assert!(json.contains(r#""owner":"ip:10.0.0.1""#));
+        assert!(json.contains(r#""custom":true"#));
+    }
+
+    #[test]
+    fn redirect_is_stored_as_status_code() {
+        let url = Url::new("https://example.com", "k").with_redirect(Some(Redirect::Permanent));
+        let json = serde_json::to_string(&url).unwrap();
+        assert!(json.contains(r#""redirect":308"#));
+        let restored: Url = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.redirect(), Some(Redirect::Permanent));
+    }
+
+    #[test]
+    fn forwarding_defaults_to_disabled() {
+        let json = r#"{"url":"https://example.com","key":"old"}"#;
+        let url: Url = serde_json::from_str(json).unwrap();
+        assert!(!url.forward_query());
+        assert!(!url.forward_path());
+
+        let url = Url::new("https://example.com", "k").with_forwarding(true, false);
+        assert!(url.forward_query());
+        assert!(!url.forward_path());
+    }
+
+    #[test]
+    fn password_is_hashed() {
+        let url = Url::new("https://example.com", "k").with_password(Some("s3cret"));
+        let json = serde_json::to_string(&url).unwrap();
+        assert!(!json.contains("s3cret"));
+        let restored: Url = serde_json::from_str(&json).unwrap();
+        assert!(restored.password().unwrap().verify("s3cret"));
+    }
+
+    #[test]
+    fn max_clicks_roundtrip() {
+        let url = Url::new("https://example.com", "k").with_max_clicks(Some(1));
+        let json = serde_json::to_string(&url).unwrap();
+        assert!(json.contains(r#""max_clicks":1"#));
+        let restored: Url = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.max_clicks(), Some(1));
+        assert_eq!(Url::new("https://example.com", "k").max_clicks(), None);
+    }
+
+    #[test]
+    fn window_without_bounds_is_always_active() {
+        let url = Url::new("https://example.com", "k");
+        assert_eq!(url.window(DateTime::now()), Window::Active);
+    }
+
+    #[test]
+    fn window_bounds() {
+        let start = DateTime::from_millis(1_000);
+        let end = DateTime::from_millis(2_000);
+        let url = Url::new("https://example.com", "k").with_window(Some(start), Some(end));
+        assert_eq!(url.window(DateTime::from_millis(999)), Window::Pending);
+        assert_eq!(url.window(start), Window::Active);
+        assert_eq!(url.window(end), Window::Active);
+        assert_eq!(url.window(DateTime::from_millis(2_001)), Window::Expired);
+    }
+
+    #[test]
+    fn targets_roundtrip() {
+        let targets: Vec<Target> = serde_json::from_str(
+            r#"[{"url":"https://a","weight":70},{"url":"https://b","weight":30}]"#,
+        )
+        .unwrap();
+        let url = Url::new("https://example.com", "k").with_targets(targets.clone(), true);
+        let json = serde_json::to_string(&url).unwrap();
+        let restored: Url = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.targets(), targets.as_slice());
+        assert!(restored.sticky());
+
+        let plain = serde_json::to_string(&Url::new("https://example.com", "k")).unwrap();
+        assert!(!plain.contains("targets"));
+    }
+
+    #[test]
+    fn rules_make_link_dynamic() {
+        let url = Url::new("https://example.com", "k");
+        assert!(!url.is_dynamic());
+        let rules: Vec<Rule> =
+            serde_json::from_str(r#"[{"os":"android","url":"https://play.google.com"}]"#).unwrap();
+        let url = url.with_rules(rules);
+        assert!(url.is_dynamic());
+        assert_eq!(url.rules().len(), 1);
+    }
+
+    #[test]
+    fn open_graph_makes_link_dynamic() {
+        let url = Url::new("https://example.com", "k");
+        assert!(!url.has_open_graph());
+        let url = url.with_open_graph(Some("Launch"), None, None);
+        assert!(url.has_open_graph());
+        assert!(url.is_dynamic());
+        assert_eq!(url.title(), Some("Launch"));
+        assert_eq!(url.image(), None);
+    }
+
+    #[test]
+    fn languages_make_link_dynamic() {
+        let languages: Vec<Localized> =
+            serde_json::from_str(r#"[{"language":"de","url":"https://example.de"}]"#).unwrap();
+        let url = Url::new("https://example.com", "k").with_languages(languages);
+        assert!(url.is_dynamic());
+        assert_eq!(url.languages()[0].language(), "de");
+    }
+
     #[test]
     fn debug_format() {
         let url = Url::new("https://example.com", "key");
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Google Analytics campaign parameters.
+// Stored next to the link so it can be reported by campaign, and merged into
+// the destination query string when the link is created.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Utm {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    medium: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    term: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+impl Utm {
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Query parameter pairs in their conventional order, skipping unset fields.
+    pub fn pairs(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("utm_source", &self.source),
+            ("utm_medium", &self.medium),
+            ("utm_campaign", &self.campaign),
+            ("utm_term", &self.term),
+            ("utm_content", &self.content),
+        ]
+        .into_iter()
+        // `filter_map` drops `None` fields while unwrapping the `Some` ones.
+        .filter_map(|(name, value)| value.as_deref().map(|v| (name, v)))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_skip_unset_fields() {
+        let utm: Utm =
+            serde_json::from_str(r#"{"source":"newsletter","campaign":"spring"}"#).unwrap();
+        assert_eq!(
+            utm.pairs(),
+            vec![("utm_source", "newsletter"), ("utm_campaign", "spring")]
+        );
+    }
+
+    #[test]
+    fn empty_utm_has_no_pairs() {
+        assert!(Utm::default().pairs().is_empty());
+    }
+
+    #[test]
+    fn unset_fields_are_not_serialized() {
+        let utm: Utm = serde_json::from_str(r#"{"source":"x"}"#).unwrap();
+        assert_eq!(serde_json::to_string(&utm).unwrap(), r#"{"source":"x"}"#);
+    }
+}
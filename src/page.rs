@@ -0,0 +1,246 @@
+// Small HTML pages served instead of a redirect.
+// Pages are built with `format!` - there are few enough of them that a
+// template engine would be more weight than help.
+
+/// Escape text for safe use in HTML element content and quoted attributes.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Shared document skeleton. `head` and `body` must already be escaped.
+fn document(title: &str, head: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>{title}</title>
+{head}</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = escape(title),
+    )
+}
+
+/// Form asking for the password of a protected link.
+/// It posts back to the current URL so query strings and path suffixes survive.
+pub fn password(key: &str, failed: bool) -> String {
+    let error = if failed {
+        "<p><strong>Wrong password, try again.</strong></p>\n"
+    } else {
+        ""
+    };
+    document(
+        "Password required",
+        "",
+        &format!(
+            r#"<h1>Password required</h1>
+<p>The link <code>{key}</code> is protected.</p>
+{error}<form method="post">
+<input type="password" name="password" autofocus required>
+<button type="submit">Continue</button>
+</form>"#,
+            key = escape(key),
+        ),
+    )
+}
+
+/// Open Graph and Twitter card metadata for link-unfurling bots.
+/// `url` is the short URL, so the preview card points back at the link.
+pub fn open_graph(
+    url: &str,
+    title: Option<&str>,
+    description: Option<&str>,
+    image: Option<&str>,
+) -> String {
+    let title = title.unwrap_or(url);
+    // A large card only makes sense with an image to fill it.
+    let card = if image.is_some() {
+        "summary_large_image"
+    } else {
+        "summary"
+    };
+
+    let mut head = String::new();
+    let mut meta = |attr: &str, name: &str, content: &str| {
+        head.push_str(&format!(
+            "<meta {attr}=\"{name}\" content=\"{content}\">\n",
+            content = escape(content),
+        ));
+    };
+    meta("property", "og:type", "website");
+    meta("property", "og:url", url);
+    meta("property", "og:title", title);
+    meta("name", "twitter:card", card);
+    meta("name", "twitter:title", title);
+    if let Some(description) = description {
+        meta("property", "og:description", description);
+        meta("name", "twitter:description", description);
+    }
+    if let Some(image) = image {
+        meta("property", "og:image", image);
+        meta("name", "twitter:image", image);
+    }
+
+    document(
+        title,
+        &head,
+        &format!(
+            r#"<h1>{title}</h1>
+<p><a href="{url}">{url}</a></p>"#,
+            title = escape(title),
+            url = escape(url),
+        ),
+    )
+}
+
+/// Interstitial showing where a link leads before the visitor continues.
+/// Only http(s) destinations get a link; anything else could run script.
+pub fn preview(key: &str, location: &str) -> String {
+    let parsed = url::Url::parse(location).ok();
+    let domain = parsed
+        .as_ref()
+        .and_then(|u| u.host_str())
+        .unwrap_or(location);
+    let safe = parsed
+        .as_ref()
+        .is_some_and(|u| matches!(u.scheme(), "http" | "https"));
+    let action = if safe {
+        format!(
+            r#"<p><a href="{location}" rel="noreferrer">Continue to {domain}</a></p>"#,
+            location = escape(location),
+            domain = escape(domain),
+        )
+    } else {
+        String::from("<p>This destination cannot be opened from here.</p>")
+    };
+    document(
+        "Link preview",
+        "",
+        &format!(
+            r#"<h1>Link preview</h1>
+<p>The link <code>{key}</code> leads to <strong>{domain}</strong>:</p>
+<p><code>{location}</code></p>
+{action}"#,
+            key = escape(key),
+            domain = escape(domain),
+            location = escape(location),
+        ),
+    )
+}
+
+// Swagger UI is loaded from a CDN unless the files are served locally. The
+// version is pinned exactly, so the page never picks up a release nobody
+// has looked at.
+const SWAGGER_UI: &str = "https://unpkg.com/swagger-ui-dist@5.17.14";
+
+/// Interactive API documentation rendering the OpenAPI document at `spec`,
+/// with the Swagger UI files under `assets`, or from the CDN without it.
+/// Relative URLs keep working when the service is mounted under a prefix.
+pub fn api_docs(spec: &str, assets: Option<&str>) -> String {
+    let assets = escape(assets.unwrap_or(SWAGGER_UI));
+    document(
+        "API documentation",
+        &format!(
+            r#"<link rel="stylesheet" href="{assets}/swagger-ui.css">
+"#
+        ),
+        &format!(
+            r##"<div id="swagger-ui"></div>
+<script src="{assets}/swagger-ui-bundle.js" crossorigin></script>
+<script>
+window.onload = () => SwaggerUIBundle({{ url: "{spec}", dom_id: "#swagger-ui" }});
+</script>"##,
+            spec = escape(spec),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn password_page_escapes_key() {
+        let html = password("<script>", false);
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("Wrong password"));
+    }
+
+    #[test]
+    fn password_page_reports_failure() {
+        assert!(password("abc", true).contains("Wrong password"));
+    }
+
+    #[test]
+    fn open_graph_has_meta_tags() {
+        let html = open_graph(
+            "https://sho.rt/api/abc",
+            Some("Launch \"day\""),
+            Some("All the news"),
+            Some("https://example.com/card.png"),
+        );
+        assert!(html.contains(r#"<meta property="og:title" content="Launch &quot;day&quot;">"#));
+        assert!(html.contains(r#"<meta property="og:url" content="https://sho.rt/api/abc">"#));
+        assert!(html.contains(r#"<meta name="twitter:card" content="summary_large_image">"#));
+        assert!(html.contains(r#"<meta property="og:description" content="All the news">"#));
+    }
+
+    #[test]
+    fn open_graph_without_image() {
+        let html = open_graph("https://sho.rt/api/abc", None, None, None);
+        assert!(html.contains(r#"<meta name="twitter:card" content="summary">"#));
+        assert!(html.contains(r#"<meta property="og:title" content="https://sho.rt/api/abc">"#));
+        assert!(!html.contains("og:image"));
+    }
+
+    #[test]
+    fn preview_shows_domain_and_link() {
+        let html = preview("abc", "https://example.com/a?b=1&c=2");
+        assert!(html.contains("<strong>example.com</strong>"));
+        assert!(html.contains(r#"href="https://example.com/a?b=1&amp;c=2""#));
+    }
+
+    #[test]
+    fn api_docs_load_the_spec() {
+        let html = api_docs("api/openapi.json", None);
+        assert!(html.contains(r#"url: "api/openapi.json""#));
+        assert!(html.contains("swagger-ui-dist@5.17.14/swagger-ui-bundle.js"));
+
+        let html = api_docs("api/openapi.json", Some("docs"));
+        assert!(html.contains(r#"src="docs/swagger-ui-bundle.js""#));
+        assert!(!html.contains("unpkg"));
+    }
+
+    #[test]
+    fn preview_refuses_script_links() {
+        let html = preview("abc", "javascript:alert(1)");
+        assert!(!html.contains("href"));
+        assert!(html.contains("javascript:alert(1)"));
+    }
+}
@@ -0,0 +1,326 @@
+// QR code rendering for short links.
+// Modules are drawn straight into PNG pixels or SVG paths, which avoids
+// pulling in a general-purpose imaging library.
+
+use std::fmt;
+
+use qrcode::types::QrError;
+use qrcode::{EcLevel, QrCode};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl Format {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Png => "image/png",
+            Format::Svg => "image/svg+xml",
+        }
+    }
+}
+
+// Error correction level: roughly 7% (L), 15% (M), 25% (Q) or 30% (H) of the
+// symbol can be damaged and still scan. Higher levels make denser codes.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub enum Level {
+    #[serde(alias = "l")]
+    L,
+    #[default]
+    #[serde(alias = "m")]
+    M,
+    #[serde(alias = "q")]
+    Q,
+    #[serde(alias = "h")]
+    H,
+}
+
+// `From` lets `.into()` convert our serde-friendly enum into the library's.
+impl From<Level> for EcLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::L => EcLevel::L,
+            Level::M => EcLevel::M,
+            Level::Q => EcLevel::Q,
+            Level::H => EcLevel::H,
+        }
+    }
+}
+
+// 24-bit colour, written as `rrggbb` or `rgb` with an optional leading `#`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb([u8; 3]);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb([0, 0, 0]);
+    pub const WHITE: Rgb = Rgb([255, 255, 255]);
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        // The short form doubles each digit: "f0c" is "ff00cc".
+        let full: String = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 => hex.to_string(),
+            _ => return None,
+        };
+        let mut rgb = [0u8; 3];
+        hex::decode_to_slice(full, &mut rgb).ok()?;
+        Some(Rgb(rgb))
+    }
+
+    fn hex(self) -> String {
+        format!("#{}", hex::encode(self.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    // Requested edge length in pixels; PNG output grows when the code needs more.
+    pub size: u32,
+    pub level: Level,
+    // Quiet zone around the code, in modules. Scanners expect at least 4.
+    pub margin: u32,
+    pub foreground: Rgb,
+    pub background: Rgb,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            size: 256,
+            level: Level::M,
+            margin: 4,
+            foreground: Rgb::BLACK,
+            background: Rgb::WHITE,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    // The data does not fit into a QR code at the chosen level.
+    Encode(QrError),
+    Png(png::EncodingError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Encode(err) => write!(f, "encoding QR code failed: {}", err),
+            Error::Png(err) => write!(f, "writing PNG failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Dark modules of a code including its quiet zone, row by row.
+struct Grid {
+    width: u32,
+    dark: Vec<bool>,
+}
+
+impl Grid {
+    fn new(data: &str, style: &Style) -> Result<Self, Error> {
+        let code = QrCode::with_error_correction_level(data.as_bytes(), style.level.into())
+            .map_err(Error::Encode)?;
+        // QR codes are at most 177 modules wide, so the casts cannot truncate.
+        let inner = code.width() as u32;
+        let width = inner + 2 * style.margin;
+        let colors = code.to_colors();
+
+        let dark = (0..width * width)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                // `checked_sub` yields None inside the quiet zone on the top/left.
+                match (x.checked_sub(style.margin), y.checked_sub(style.margin)) {
+                    (Some(x), Some(y)) if x < inner && y < inner => {
+                        colors[(y * inner + x) as usize] == qrcode::Color::Dark
+                    }
+                    _ => false,
+                }
+            })
+            .collect();
+        Ok(Grid { width, dark })
+    }
+
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        self.dark[(y * self.width + x) as usize]
+    }
+}
+
+/// Render `data` as a PNG image.
+/// Modules are scaled by a whole number of pixels so they stay sharp; any
+/// remainder of the requested size is filled with the background colour.
+pub fn png(data: &str, style: &Style) -> Result<Vec<u8>, Error> {
+    let grid = Grid::new(data, style)?;
+    let scale = (style.size / grid.width).max(1);
+    let size = style.size.max(grid.width * scale);
+    let offset = (size - grid.width * scale) / 2;
+
+    let mut pixels = Vec::with_capacity((size * size * 3) as usize);
+    for py in 0..size {
+        for px in 0..size {
+            let module = |p: u32| p.checked_sub(offset).map(|p| p / scale);
+            let dark = match (module(px), module(py)) {
+                (Some(x), Some(y)) if x < grid.width && y < grid.width => grid.is_dark(x, y),
+                _ => false,
+            };
+            let color = if dark {
+                style.foreground
+            } else {
+                style.background
+            };
+            pixels.extend_from_slice(&color.0);
+        }
+    }
+
+    let mut out = Vec::new();
+    // The encoder borrows `out` mutably; the inner block ends that borrow
+    // before `out` is returned.
+    {
+        let mut encoder = png::Encoder::new(&mut out, size, size);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(Error::Png)?;
+        writer.write_image_data(&pixels).map_err(Error::Png)?;
+        writer.finish().map_err(Error::Png)?;
+    }
+    Ok(out)
+}
+
+/// Render `data` as an SVG document of exactly `style.size` pixels.
+/// The view box is measured in modules, so the image scales without blurring.
+pub fn svg(data: &str, style: &Style) -> Result<String, Error> {
+    let grid = Grid::new(data, style)?;
+
+    // One path with a unit square per dark module keeps the document small.
+    let mut path = String::new();
+    for y in 0..grid.width {
+        for x in 0..grid.width {
+            if grid.is_dark(x, y) {
+                path.push_str(&format!("M{x} {y}h1v1h-1z"));
+            }
+        }
+    }
+
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{size}" height="{size}" viewBox="0 0 {width} {width}" shape-rendering="crispEdges">
+<rect width="100%" height="100%" fill="{background}"/>
+<path d="{path}" fill="{foreground}"/>
+</svg>
+"#,
+        size = style.size,
+        width = grid.width,
+        background = style.background.hex(),
+        foreground = style.foreground.hex(),
+    ))
+}
+
+/// Stable identifier of the image `png` or `svg` would render, for use as an
+/// ETag. It only hashes the inputs, so a cached copy can be confirmed without
+/// rendering. The crate version is included because rendering may change
+/// between releases.
+pub fn fingerprint(data: &str, style: &Style, format: Format) -> String {
+    let inputs = format!(
+        "{}\n{data}\n{style:?}\n{format:?}",
+        env!("CARGO_PKG_VERSION")
+    );
+    hex::encode(&Sha256::digest(inputs.as_bytes())[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = "https://example.com/api/abc123";
+
+    #[test]
+    fn fingerprint_follows_the_inputs() {
+        let style = Style::default();
+        let base = fingerprint(DATA, &style, Format::Png);
+        assert_eq!(base, fingerprint(DATA, &style, Format::Png));
+        assert_ne!(base, fingerprint(DATA, &style, Format::Svg));
+        assert_ne!(
+            base,
+            fingerprint("https://example.com/api/x", &style, Format::Png)
+        );
+        let larger = Style { size: 512, ..style };
+        assert_ne!(base, fingerprint(DATA, &larger, Format::Png));
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(Rgb::parse("#000000"), Some(Rgb::BLACK));
+        assert_eq!(Rgb::parse("fff"), Some(Rgb::WHITE));
+        assert_eq!(Rgb::parse("#1a2B3c"), Some(Rgb([0x1a, 0x2b, 0x3c])));
+        assert_eq!(Rgb::parse("#12345"), None);
+        assert_eq!(Rgb::parse("zzzzzz"), None);
+    }
+
+    #[test]
+    fn grid_includes_quiet_zone() {
+        let style = Style::default();
+        let grid = Grid::new(DATA, &style).unwrap();
+        let inner = QrCode::new(DATA.as_bytes()).unwrap().width() as u32;
+        assert_eq!(grid.width, inner + 8);
+        // The quiet zone is light; finder patterns start dark in the corner.
+        assert!(!grid.is_dark(0, 0));
+        assert!(grid.is_dark(4, 4));
+    }
+
+    #[test]
+    fn png_has_requested_size() {
+        let bytes = png(DATA, &Style::default()).unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+        let reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().width, 256);
+        assert_eq!(reader.info().height, 256);
+    }
+
+    #[test]
+    fn png_grows_for_tiny_sizes() {
+        let style = Style {
+            size: 10,
+            ..Style::default()
+        };
+        let bytes = png(DATA, &style).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(bytes))
+            .read_info()
+            .unwrap();
+        assert!(reader.info().width > 10);
+    }
+
+    #[test]
+    fn svg_uses_colors() {
+        let style = Style {
+            foreground: Rgb([0x11, 0x22, 0x33]),
+            ..Style::default()
+        };
+        let doc = svg(DATA, &style).unwrap();
+        assert!(doc.contains(r#"width="256""#));
+        assert!(doc.contains("#112233"));
+        assert!(doc.contains("#ffffff"));
+    }
+
+    #[test]
+    fn higher_level_makes_denser_code() {
+        let low = Grid::new(DATA, &Style::default()).unwrap();
+        let high = Grid::new(
+            DATA,
+            &Style {
+                level: Level::H,
+                ..Style::default()
+            },
+        )
+        .unwrap();
+        assert!(high.width > low.width);
+    }
+}
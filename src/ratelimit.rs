@@ -0,0 +1,222 @@
+// Token-bucket rate limiting keyed by client identity.
+// Each client gets a bucket that holds up to `capacity` tokens and refills
+// continuously at `refill` tokens per second. A request spends one token.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hashlink::LruCache;
+
+use crate::setting;
+
+// Upper bound on tracked clients. Past it the least recently seen client's
+// bucket is dropped, in constant time, so memory stays bounded however many
+// distinct clients show up.
+const MAX_BUCKETS: usize = 100_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// `Arc<Mutex<...>>` shares the buckets between all worker threads.
+// Cloning the limiter clones the `Arc`, not the map.
+#[derive(Clone)]
+pub struct Limiter {
+    capacity: f64,
+    refill: f64,
+    buckets: Arc<Mutex<LruCache<String, Bucket>>>,
+}
+
+impl Limiter {
+    pub fn new(limit: &setting::Limit) -> Self {
+        Limiter {
+            capacity: f64::from(limit.capacity()),
+            refill: limit.refill(),
+            buckets: Arc::new(Mutex::new(LruCache::new(MAX_BUCKETS))),
+        }
+    }
+
+    // A limiter with zero capacity never throttles.
+    pub fn unlimited() -> Self {
+        Limiter {
+            capacity: 0.0,
+            refill: 0.0,
+            buckets: Arc::new(Mutex::new(LruCache::new(MAX_BUCKETS))),
+        }
+    }
+
+    /// Spend one token from the client's bucket.
+    /// Returns `Err` with the time until a token is available when throttled.
+    pub fn check(&self, client: &str) -> Result<(), Duration> {
+        self.check_at(client, Instant::now(), true)
+    }
+
+    /// Like `check`, but leaves the bucket untouched.
+    /// Used when only some outcomes (e.g. failed attempts) should cost a token.
+    pub fn peek(&self, client: &str) -> Result<(), Duration> {
+        self.check_at(client, Instant::now(), false)
+    }
+
+    // Separated from `check` so tests can control the clock.
+    fn check_at(&self, client: &str, now: Instant, spend: bool) -> Result<(), Duration> {
+        if self.capacity <= 0.0 {
+            return Ok(());
+        }
+
+        // A poisoned lock only means another thread panicked mid-update;
+        // the buckets are still usable, so recover the guard.
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        // `get_mut` marks the bucket as most recently used; `insert` evicts
+        // the least recently used one once the cache is full.
+        if buckets.get_mut(client).is_none() {
+            buckets.insert(
+                client.to_string(),
+                Bucket {
+                    tokens: self.capacity,
+                    updated: now,
+                },
+            );
+        }
+        let bucket = buckets.get_mut(client).expect("bucket was just inserted");
+
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            if spend {
+                bucket.tokens -= 1.0;
+            }
+            return Ok(());
+        }
+
+        if self.refill <= 0.0 {
+            // Without refill the bucket never recovers.
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill))
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill).min(self.capacity)
+    }
+}
+
+/// Seconds for the `Retry-After` header, rounded up so clients never retry early.
+/// `None` when the bucket never refills, since no retry would ever succeed.
+pub fn retry_after(wait: Duration) -> Option<u64> {
+    if wait == Duration::MAX {
+        return None;
+    }
+    Some(
+        wait.as_secs()
+            .saturating_add(u64::from(wait.subsec_nanos() > 0)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(capacity: f64, refill: f64) -> Limiter {
+        Limiter {
+            capacity,
+            refill,
+            buckets: Arc::new(Mutex::new(LruCache::new(MAX_BUCKETS))),
+        }
+    }
+
+    #[test]
+    fn allows_up_to_capacity() {
+        let l = limiter(3.0, 1.0);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(l.check_at("client", now, true).is_ok());
+        }
+        assert!(l.check_at("client", now, true).is_err());
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let l = limiter(1.0, 2.0);
+        let now = Instant::now();
+        assert!(l.check_at("client", now, true).is_ok());
+        assert!(l.check_at("client", now, true).is_err());
+        // 2 tokens per second - one token after half a second.
+        assert!(
+            l.check_at("client", now + Duration::from_millis(500), true)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn reports_wait_time() {
+        let l = limiter(1.0, 0.5);
+        let now = Instant::now();
+        l.check_at("client", now, true).unwrap();
+        let wait = l.check_at("client", now, true).unwrap_err();
+        assert_eq!(retry_after(wait), Some(2));
+    }
+
+    #[test]
+    fn no_retry_after_without_refill() {
+        let l = limiter(1.0, 0.0);
+        let now = Instant::now();
+        l.check_at("client", now, true).unwrap();
+        let wait = l.check_at("client", now, true).unwrap_err();
+        assert_eq!(retry_after(wait), None);
+    }
+
+    #[test]
+    fn clients_are_independent() {
+        let l = limiter(1.0, 1.0);
+        let now = Instant::now();
+        assert!(l.check_at("a", now, true).is_ok());
+        assert!(l.check_at("b", now, true).is_ok());
+        assert!(l.check_at("a", now, true).is_err());
+    }
+
+    #[test]
+    fn peek_does_not_spend() {
+        let l = limiter(1.0, 1.0);
+        let now = Instant::now();
+        for _ in 0..5 {
+            assert!(l.check_at("client", now, false).is_ok());
+        }
+        assert!(l.check_at("client", now, true).is_ok());
+        assert!(l.check_at("client", now, false).is_err());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_seen_client() {
+        let l = Limiter {
+            capacity: 1.0,
+            refill: 0.0,
+            buckets: Arc::new(Mutex::new(LruCache::new(2))),
+        };
+        let now = Instant::now();
+        l.check_at("a", now, true).unwrap();
+        l.check_at("b", now, true).unwrap();
+        // Touching `a` makes `b` the oldest, so `c` pushes `b` out.
+        assert!(l.check_at("a", now, false).is_err());
+        l.check_at("c", now, true).unwrap();
+        assert!(l.check_at("a", now, false).is_err());
+        assert!(l.check_at("b", now, false).is_ok());
+    }
+
+    #[test]
+    fn unlimited_never_throttles() {
+        let l = Limiter::unlimited();
+        for _ in 0..1000 {
+            assert!(l.check("client").is_ok());
+        }
+    }
+
+    #[test]
+    fn retry_after_rounds_up() {
+        assert_eq!(retry_after(Duration::from_millis(1)), Some(1));
+        assert_eq!(retry_after(Duration::from_secs(3)), Some(3));
+    }
+}
@@ -1,14 +1,262 @@
-use serde::Deserialize;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 // `as` keyword creates an alias to avoid name collision with our `Url` struct.
 use url::Url as ParsedUrl;
+use url::form_urlencoded;
 
-// Only `Deserialize` needed - this struct receives data, never sends it.
-#[derive(Debug, Deserialize)]
+use crate::destination;
+use crate::export;
+use crate::import;
+use crate::model;
+use crate::qr;
+use crate::store;
+
+// Longest accepted value for a single UTM parameter.
+const UTM_MAX_LENGTH: usize = 128;
+// Longest accepted link password.
+const PASSWORD_MAX_LENGTH: usize = 256;
+// Most destinations a split link may rotate among.
+const MAX_TARGETS: usize = 16;
+// Most redirect rules a link may carry.
+const MAX_RULES: usize = 32;
+// Longest accepted Open Graph title and description.
+const TITLE_MAX_LENGTH: usize = 256;
+const DESCRIPTION_MAX_LENGTH: usize = 1024;
+// Most tags per link and longest tag.
+const MAX_TAGS: usize = 32;
+const TAG_MAX_LENGTH: usize = 64;
+// Bounds of a QR code's edge length in pixels.
+const QR_MIN_SIZE: u32 = 64;
+const QR_MAX_SIZE: u32 = 2048;
+// Widest accepted QR quiet zone, in modules.
+const QR_MAX_MARGIN: u32 = 16;
+// Names of the API's own routes under `/api`, which would shadow a link.
+const RESERVED_NAMES: [&str; 6] = [
+    "urls",
+    "import",
+    "export",
+    "openapi.json",
+    // `GET /urls/{name}` would be answered by these listings instead.
+    "usage",
+    "campaigns",
+];
+
+// Newtype around a secret string. `#[serde(transparent)]` deserializes it
+// from a plain JSON string, and the manual `Debug` keeps it out of logs.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+// The server deserializes it; `Serialize` and the builder below let the
+// `fesghel-client` SDK send it. `Default` is an empty request.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct Url {
     url: String,
     // `Option<T>` represents an optional value: Some(value) or None.
     // Serde treats missing JSON fields as None for Option types.
     name: Option<String>,
+    // Redirect status code (301, 302, 307 or 308).
+    redirect: Option<u16>,
+    // `#[serde(default)]` makes a missing field `false` without wrapping in Option.
+    // Merge the visitor's query string into the destination on redirect.
+    #[serde(default)]
+    forward_query: bool,
+    // Append extra path segments after the key to the destination path.
+    #[serde(default)]
+    forward_path: bool,
+    // Show an interstitial page with the destination instead of redirecting.
+    #[serde(default)]
+    preview: bool,
+    // Open Graph metadata for link unfurling in chat apps.
+    title: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    // Free-form labels.
+    #[serde(default)]
+    tags: Vec<String>,
+    // Campaign parameters merged into the destination on creation.
+    utm: Option<model::Utm>,
+    // Visitors must enter this before being redirected.
+    // Documented as the plain string it is sent as.
+    #[schema(value_type = Option<String>)]
+    password: Option<Secret>,
+    // Number of redirects before the link stops working; 1 for single-use links.
+    max_clicks: Option<u32>,
+    // Activation window as RFC 3339 timestamps, e.g. "2026-01-01T00:00:00Z".
+    not_before: Option<String>,
+    not_after: Option<String>,
+    // Destination used outside the activation window.
+    fallback: Option<String>,
+    // Weighted destinations for split (A/B) tests.
+    #[serde(default)]
+    targets: Vec<model::Target>,
+    // Keep each visitor on the target they were first sent to (via a cookie).
+    #[serde(default)]
+    sticky: bool,
+    // Destinations chosen by the visitor's device, evaluated in order.
+    #[serde(default)]
+    rules: Vec<model::Rule>,
+    // Localized destinations chosen from the visitor's `Accept-Language`.
+    #[serde(default)]
+    languages: Vec<model::Localized>,
+}
+
+// Form submitted from the password page of a protected link.
+#[derive(Debug, Deserialize)]
+pub struct Unlock {
+    password: Secret,
+}
+
+impl Unlock {
+    pub fn password(&self) -> &str {
+        self.password.expose()
+    }
+}
+
+// Query string of the import endpoint, e.g. `?format=jsonl&skip=120000`.
+#[derive(Debug, Deserialize)]
+pub struct Import {
+    #[serde(default)]
+    format: import::Format,
+    // Lines already handled by an earlier, interrupted run.
+    #[serde(default)]
+    skip: usize,
+    owner: Option<String>,
+    // Export layout of the shortener the links come from.
+    #[serde(default)]
+    source: import::Source,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+impl Import {
+    pub fn format(&self) -> import::Format {
+        self.format
+    }
+
+    pub fn skip(&self) -> usize {
+        self.skip
+    }
+
+    pub fn owner(&self) -> &str {
+        self.owner.as_deref().unwrap_or(import::DEFAULT_OWNER)
+    }
+
+    pub fn source(&self) -> import::Source {
+        self.source
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+// Query string of the link listing, e.g. `?limit=50`.
+#[derive(Debug, Deserialize)]
+pub struct List {
+    limit: Option<u32>,
+}
+
+impl List {
+    // Newest 20 links by default, at most 1000.
+    pub fn limit(&self) -> i64 {
+        i64::from(self.limit.unwrap_or(20).clamp(1, 1000))
+    }
+}
+
+// Query string of the export endpoint, e.g. `?data=clicks&format=csv`.
+#[derive(Debug, Deserialize)]
+pub struct Export {
+    #[serde(default)]
+    data: export::Data,
+    // JSON Lines unless asked otherwise, as only they carry every field.
+    format: Option<import::Format>,
+}
+
+impl Export {
+    pub fn data(&self) -> export::Data {
+        self.data
+    }
+
+    pub fn format(&self) -> import::Format {
+        self.format.unwrap_or(import::Format::Jsonl)
+    }
+}
+
+// Query string of the QR code endpoint, e.g. `?format=svg&size=512&ecc=H&fg=%23336699`.
+// Every parameter is optional and falls back to `qr::Style::default()`.
+#[derive(Debug, Default, Deserialize)]
+pub struct Qr {
+    #[serde(default)]
+    format: qr::Format,
+    size: Option<u32>,
+    ecc: Option<qr::Level>,
+    margin: Option<u32>,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+impl Qr {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self
+            .size
+            .is_some_and(|s| !(QR_MIN_SIZE..=QR_MAX_SIZE).contains(&s))
+        {
+            return Err(ValidationError::InvalidQr(
+                "size",
+                "size must be between 64 and 2048",
+            ));
+        }
+        if self.margin.is_some_and(|m| m > QR_MAX_MARGIN) {
+            return Err(ValidationError::InvalidQr(
+                "margin",
+                "margin must be at most 16",
+            ));
+        }
+        // `find` stops at the first colour that is present but does not parse.
+        if let Some((field, _)) = [("fg", &self.fg), ("bg", &self.bg)]
+            .into_iter()
+            .find(|(_, c)| c.as_deref().is_some_and(|c| qr::Rgb::parse(c).is_none()))
+        {
+            return Err(ValidationError::InvalidQr(
+                field,
+                "colours must be hex codes like #1a2b3c",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn format(&self) -> qr::Format {
+        self.format
+    }
+
+    // Only meaningful after `validate()`; invalid colours fall back to the defaults.
+    pub fn style(&self) -> qr::Style {
+        let default = qr::Style::default();
+        let color = |c: &Option<String>, fallback| {
+            c.as_deref().and_then(qr::Rgb::parse).unwrap_or(fallback)
+        };
+        qr::Style {
+            size: self.size.unwrap_or(default.size),
+            level: self.ecc.unwrap_or(default.level),
+            margin: self.margin.unwrap_or(default.margin),
+            foreground: color(&self.fg, default.foreground),
+            background: color(&self.bg, default.background),
+        }
+    }
 }
 
 // `enum` in Rust is an algebraic data type (sum type).
@@ -18,6 +266,23 @@ pub enum ValidationError {
     // Variant holding associated data (the parse error).
     // This pattern enables rich error types with context.
     InvalidUrl(url::ParseError),
+    InvalidName(&'static str),
+    InvalidRedirect(u16),
+    // `&'static str` - the reason is always a string literal.
+    InvalidUtm(&'static str),
+    InvalidPassword(&'static str),
+    InvalidMaxClicks,
+    // Holds the name of the offending field.
+    InvalidTimestamp(&'static str),
+    InvalidWindow,
+    InvalidFallback(url::ParseError),
+    InvalidTargets(&'static str),
+    InvalidRules(&'static str),
+    InvalidLanguages(&'static str),
+    // The offending field, then the reason.
+    InvalidQr(&'static str, &'static str),
+    InvalidOpenGraph(&'static str, &'static str),
+    InvalidTags(&'static str),
 }
 
 // Implementing Display for custom error messages.
@@ -28,29 +293,452 @@ impl std::fmt::Display for ValidationError {
         match self {
             // Pattern destructuring: extracts `e` from the variant.
             ValidationError::InvalidUrl(e) => write!(f, "invalid URL: {}", e),
+            ValidationError::InvalidName(reason) => write!(f, "invalid name: {}", reason),
+            ValidationError::InvalidRedirect(status) => {
+                write!(f, "invalid redirect status: {}", status)
+            }
+            ValidationError::InvalidUtm(reason) => write!(f, "invalid UTM parameters: {}", reason),
+            ValidationError::InvalidPassword(reason) => write!(f, "invalid password: {}", reason),
+            ValidationError::InvalidMaxClicks => write!(f, "max_clicks must be positive"),
+            ValidationError::InvalidTimestamp(field) => {
+                write!(f, "invalid {}: expected an RFC 3339 timestamp", field)
+            }
+            ValidationError::InvalidWindow => write!(f, "not_before must not be after not_after"),
+            ValidationError::InvalidFallback(e) => write!(f, "invalid fallback URL: {}", e),
+            ValidationError::InvalidTargets(reason) => write!(f, "invalid targets: {}", reason),
+            ValidationError::InvalidRules(reason) => write!(f, "invalid rules: {}", reason),
+            ValidationError::InvalidLanguages(reason) => {
+                write!(f, "invalid languages: {}", reason)
+            }
+            ValidationError::InvalidQr(_, reason) => write!(f, "invalid QR options: {}", reason),
+            ValidationError::InvalidOpenGraph(_, reason) => {
+                write!(f, "invalid Open Graph metadata: {}", reason)
+            }
+            ValidationError::InvalidTags(reason) => write!(f, "invalid tags: {}", reason),
+        }
+    }
+}
+
+impl ValidationError {
+    /// Stable machine-readable name of the error, sent as the `code` of the
+    /// problem response. Messages may be reworded; codes are not.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidUrl(_) => "invalid_url",
+            ValidationError::InvalidName(_) => "invalid_name",
+            ValidationError::InvalidRedirect(_) => "invalid_redirect",
+            ValidationError::InvalidUtm(_) => "invalid_utm",
+            ValidationError::InvalidPassword(_) => "invalid_password",
+            ValidationError::InvalidMaxClicks => "invalid_max_clicks",
+            ValidationError::InvalidTimestamp(_) => "invalid_timestamp",
+            ValidationError::InvalidWindow => "invalid_window",
+            ValidationError::InvalidFallback(_) => "invalid_fallback",
+            ValidationError::InvalidTargets(_) => "invalid_targets",
+            ValidationError::InvalidRules(_) => "invalid_rules",
+            ValidationError::InvalidLanguages(_) => "invalid_languages",
+            ValidationError::InvalidQr(..) => "invalid_qr",
+            ValidationError::InvalidOpenGraph(..) => "invalid_open_graph",
+            ValidationError::InvalidTags(_) => "invalid_tags",
+        }
+    }
+
+    /// Request field or query parameter the error is about.
+    pub fn field(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidUrl(_) => "url",
+            ValidationError::InvalidName(_) => "name",
+            ValidationError::InvalidRedirect(_) => "redirect",
+            ValidationError::InvalidUtm(_) => "utm",
+            ValidationError::InvalidPassword(_) => "password",
+            ValidationError::InvalidMaxClicks => "max_clicks",
+            ValidationError::InvalidTimestamp(field) => field,
+            // The window is checked once both ends parsed; either could be moved.
+            ValidationError::InvalidWindow => "not_before",
+            ValidationError::InvalidFallback(_) => "fallback",
+            ValidationError::InvalidTargets(_) => "targets",
+            ValidationError::InvalidRules(_) => "rules",
+            ValidationError::InvalidLanguages(_) => "languages",
+            ValidationError::InvalidQr(field, _) => field,
+            ValidationError::InvalidOpenGraph(field, _) => field,
+            ValidationError::InvalidTags(_) => "tags",
         }
     }
 }
 
 impl Url {
+    /// Request for a link to `url`, with everything else left to the server.
+    pub fn new(url: &str) -> Self {
+        Url {
+            url: url.to_string(),
+            ..Url::default()
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_redirect(mut self, status: u16) -> Self {
+        self.redirect = Some(status);
+        self
+    }
+
+    pub fn with_max_clicks(mut self, max_clicks: u32) -> Self {
+        self.max_clicks = Some(max_clicks);
+        self
+    }
+
+    // RFC 3339 timestamps, e.g. "2026-01-01T00:00:00Z".
+    pub fn with_window(mut self, not_before: Option<&str>, not_after: Option<&str>) -> Self {
+        self.not_before = not_before.map(String::from);
+        self.not_after = not_after.map(String::from);
+        self
+    }
+
+    pub fn with_password(mut self, password: &str) -> Self {
+        self.password = Some(Secret(password.to_string()));
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Build the stored link under `key`. Only meaningful after `validate()`.
+    /// The link counts as custom when the request named it.
+    pub fn to_model(&self, key: &str, owner: &str) -> model::Url {
+        // UTM parameters are baked into the stored destination at creation time.
+        model::Url::new(&self.destination(), key)
+            .with_owner(owner)
+            .with_custom(self.name() != "-")
+            .with_created_at(DateTime::now())
+            .with_redirect(self.redirect())
+            .with_forwarding(self.forward_query(), self.forward_path())
+            .with_preview(self.preview())
+            .with_open_graph(self.title(), self.description(), self.image())
+            .with_tags(self.tags().to_vec())
+            .with_utm(self.utm().cloned())
+            .with_password(self.password())
+            .with_max_clicks(self.max_clicks())
+            .with_window(self.not_before(), self.not_after())
+            .with_fallback(self.fallback())
+            .with_targets(self.targets().to_vec(), self.sticky())
+            .with_rules(self.rules().to_vec())
+            .with_languages(self.languages().to_vec())
+    }
+
     pub fn validate(&self) -> Result<(), ValidationError> {
         // `?` operator: if Result is Err, return early with that error.
         // `map_err` converts the error type before `?` propagates it.
         // Here: ParseError -> ValidationError::InvalidUrl(ParseError).
         ParsedUrl::parse(&self.url).map_err(ValidationError::InvalidUrl)?;
+        // A trailing `+` requests the preview page of a link, see `handler::url`.
+        if self.name().ends_with('+') {
+            return Err(ValidationError::InvalidName("must not end with '+'"));
+        }
+        // Names are path segments, so only characters that need no
+        // percent-encoding in a path are allowed. A `+` inside is fine.
+        if self.name().is_empty()
+            || !self
+                .name()
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~' | '+'))
+        {
+            return Err(ValidationError::InvalidName(
+                "only letters, digits, '-', '_', '.', '~' and '+' are allowed",
+            ));
+        }
+        if RESERVED_NAMES.contains(&self.name()) {
+            return Err(ValidationError::InvalidName("reserved for the API"));
+        }
+        if let Some(status) = self.redirect {
+            model::Redirect::try_from(status).map_err(ValidationError::InvalidRedirect)?;
+        }
+        if let Some(utm) = &self.utm {
+            validate_utm(utm)?;
+        }
+        if let Some(password) = self.password() {
+            if password.is_empty() {
+                return Err(ValidationError::InvalidPassword("must not be empty"));
+            }
+            if password.len() > PASSWORD_MAX_LENGTH {
+                return Err(ValidationError::InvalidPassword("too long"));
+            }
+        }
+        if self.max_clicks == Some(0) {
+            return Err(ValidationError::InvalidMaxClicks);
+        }
+        let not_before = parse_timestamp(self.not_before.as_deref(), "not_before")?;
+        let not_after = parse_timestamp(self.not_after.as_deref(), "not_after")?;
+        // Tuple pattern: only checked when both bounds are present.
+        if let (Some(start), Some(end)) = (not_before, not_after)
+            && start > end
+        {
+            return Err(ValidationError::InvalidWindow);
+        }
+        if let Some(fallback) = &self.fallback {
+            ParsedUrl::parse(fallback).map_err(ValidationError::InvalidFallback)?;
+        }
+        if !self.targets.is_empty() {
+            validate_targets(&self.targets)?;
+        }
+        if self.rules.len() > MAX_RULES {
+            return Err(ValidationError::InvalidRules("too many rules"));
+        }
+        if self
+            .rules
+            .iter()
+            .any(|r| ParsedUrl::parse(r.url()).is_err())
+        {
+            return Err(ValidationError::InvalidRules("rule URL is invalid"));
+        }
+        // ISO 3166-1 alpha-2 codes are exactly two letters.
+        if self
+            .rules
+            .iter()
+            .flat_map(|r| r.countries())
+            .any(|c| c.len() != 2 || !c.chars().all(|ch| ch.is_ascii_alphabetic()))
+        {
+            return Err(ValidationError::InvalidRules(
+                "country must be a two-letter code",
+            ));
+        }
+        validate_languages(&self.languages)?;
+        self.validate_open_graph()?;
+        if self.tags.len() > MAX_TAGS {
+            return Err(ValidationError::InvalidTags("too many tags"));
+        }
+        if self
+            .tags
+            .iter()
+            .any(|t| t.trim().is_empty() || t.chars().count() > TAG_MAX_LENGTH)
+        {
+            return Err(ValidationError::InvalidTags(
+                "tags must be 1 to 64 characters",
+            ));
+        }
         Ok(())
     }
 
+    /// The destination with UTM parameters merged into its query string.
+    pub fn destination(&self) -> String {
+        match &self.utm {
+            Some(utm) => {
+                let query = form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(utm.pairs())
+                    .finish();
+                destination::build(self.url(), None, Some(&query))
+            }
+            None => self.url().to_string(),
+        }
+    }
+
+    pub fn utm(&self) -> Option<&model::Utm> {
+        self.utm.as_ref()
+    }
+
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_ref().map(Secret::expose)
+    }
+
+    pub fn max_clicks(&self) -> Option<u32> {
+        self.max_clicks
+    }
+
+    // Only meaningful after `validate()`; unparsable timestamps are dropped here.
+    pub fn not_before(&self) -> Option<DateTime> {
+        parse_timestamp(self.not_before.as_deref(), "not_before")
+            .ok()
+            .flatten()
+    }
+
+    pub fn not_after(&self) -> Option<DateTime> {
+        parse_timestamp(self.not_after.as_deref(), "not_after")
+            .ok()
+            .flatten()
+    }
+
+    pub fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    pub fn targets(&self) -> &[model::Target] {
+        &self.targets
+    }
+
+    pub fn sticky(&self) -> bool {
+        self.sticky
+    }
+
+    pub fn rules(&self) -> &[model::Rule] {
+        &self.rules
+    }
+
+    pub fn languages(&self) -> &[model::Localized] {
+        &self.languages
+    }
+
     pub fn url(&self) -> &str {
         self.url.as_str()
     }
 
+    /// Key of the new link: the requested name, or a fresh random key.
+    pub fn key(&self) -> String {
+        match self.name() {
+            "-" => store::Url::random_key(),
+            name => name.to_string(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         // `as_deref()` converts Option<String> to Option<&str>.
         // `unwrap_or` returns the inner value or a default if None.
         // Combined: returns &str of name, or "-" if name is None.
         self.name.as_deref().unwrap_or("-")
     }
+
+    pub fn forward_query(&self) -> bool {
+        self.forward_query
+    }
+
+    pub fn forward_path(&self) -> bool {
+        self.forward_path
+    }
+
+    pub fn preview(&self) -> bool {
+        self.preview
+    }
+
+    fn validate_open_graph(&self) -> Result<(), ValidationError> {
+        // `chars().count()` counts characters, `len()` would count UTF-8 bytes.
+        if self
+            .title()
+            .is_some_and(|t| t.is_empty() || t.chars().count() > TITLE_MAX_LENGTH)
+        {
+            return Err(ValidationError::InvalidOpenGraph(
+                "title",
+                "title must be 1 to 256 characters",
+            ));
+        }
+        if self
+            .description()
+            .is_some_and(|d| d.chars().count() > DESCRIPTION_MAX_LENGTH)
+        {
+            return Err(ValidationError::InvalidOpenGraph(
+                "description",
+                "description must be at most 1024 characters",
+            ));
+        }
+        // Unfurlers only fetch images over HTTP(S).
+        if let Some(image) = self.image() {
+            let parsed = ParsedUrl::parse(image)
+                .map_err(|_| ValidationError::InvalidOpenGraph("image", "image must be a URL"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(ValidationError::InvalidOpenGraph(
+                    "image",
+                    "image must be an http(s) URL",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    // Only meaningful after `validate()`; invalid codes are dropped here.
+    pub fn redirect(&self) -> Option<model::Redirect> {
+        self.redirect
+            .and_then(|s| model::Redirect::try_from(s).ok())
+    }
+}
+
+// `Option<&str>` in, `Option<DateTime>` out - absent fields are not an error.
+fn parse_timestamp(
+    value: Option<&str>,
+    field: &'static str,
+) -> Result<Option<DateTime>, ValidationError> {
+    value
+        .map(|v| {
+            DateTime::parse_rfc3339_str(v).map_err(|_| ValidationError::InvalidTimestamp(field))
+        })
+        // `transpose` turns Option<Result<T, E>> into Result<Option<T>, E>.
+        .transpose()
+}
+
+fn validate_targets(targets: &[model::Target]) -> Result<(), ValidationError> {
+    if targets.len() > MAX_TARGETS {
+        return Err(ValidationError::InvalidTargets("too many targets"));
+    }
+    if targets.iter().any(|t| ParsedUrl::parse(t.url()).is_err()) {
+        return Err(ValidationError::InvalidTargets("target URL is invalid"));
+    }
+    // `u64` sum so large weights cannot overflow. The split samples with
+    // `u32` weights, so the total has to fit one.
+    let total = targets.iter().map(|t| u64::from(t.weight())).sum::<u64>();
+    if total == 0 {
+        return Err(ValidationError::InvalidTargets(
+            "weights must not all be zero",
+        ));
+    }
+    if total > u64::from(u32::MAX) {
+        return Err(ValidationError::InvalidTargets("weights sum too large"));
+    }
+    Ok(())
+}
+
+fn validate_languages(languages: &[model::Localized]) -> Result<(), ValidationError> {
+    if languages.len() > MAX_RULES {
+        return Err(ValidationError::InvalidLanguages("too many languages"));
+    }
+    for l in languages {
+        // BCP 47 tags are alphanumeric subtags joined by hyphens, e.g. "zh-Hant-TW".
+        let tag = l.language();
+        if tag.is_empty()
+            || tag.split('-').any(|sub| {
+                sub.is_empty() || sub.len() > 8 || !sub.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+        {
+            return Err(ValidationError::InvalidLanguages(
+                "language must be a BCP 47 tag",
+            ));
+        }
+        if ParsedUrl::parse(l.url()).is_err() {
+            return Err(ValidationError::InvalidLanguages("language URL is invalid"));
+        }
+    }
+    Ok(())
+}
+
+// `utm_source` is the one parameter analytics tools require.
+fn validate_utm(utm: &model::Utm) -> Result<(), ValidationError> {
+    if utm.source().is_none() {
+        return Err(ValidationError::InvalidUtm("source is required"));
+    }
+    for (_, value) in utm.pairs() {
+        if value.trim().is_empty() {
+            return Err(ValidationError::InvalidUtm("values must not be blank"));
+        }
+        if value.len() > UTM_MAX_LENGTH {
+            return Err(ValidationError::InvalidUtm("value is too long"));
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -63,6 +751,24 @@ mod tests {
         Url {
             url: url.to_string(),
             name: name.map(String::from),
+            redirect: None,
+            forward_query: false,
+            forward_path: false,
+            preview: false,
+            title: None,
+            description: None,
+            image: None,
+            tags: Vec::new(),
+            utm: None,
+            password: None,
+            max_clicks: None,
+            not_before: None,
+            not_after: None,
+            fallback: None,
+            targets: Vec::new(),
+            sticky: false,
+            rules: Vec::new(),
+            languages: Vec::new(),
         }
     }
 
@@ -114,12 +820,388 @@ mod tests {
         assert_eq!(url.name(), "-");
     }
 
+    #[test]
+    fn built_request_round_trips() {
+        let built = Url::new("https://example.com")
+            .with_name("docs")
+            .with_password("s3cret")
+            .with_window(None, Some("2030-01-01T00:00:00Z"));
+        let json = serde_json::to_string(&built).unwrap();
+        let url: Url = serde_json::from_str(&json).unwrap();
+        assert!(url.validate().is_ok());
+        assert_eq!(url.name(), "docs");
+        assert_eq!(url.password(), Some("s3cret"));
+    }
+
     #[test]
     fn url_returns_the_url_string() {
         let url = make_url("https://example.com/path", Some("key"));
         assert_eq!(url.url(), "https://example.com/path");
     }
 
+    #[test]
+    fn validate_accepts_redirect_codes() {
+        for status in [301, 302, 307, 308] {
+            let mut url = make_url("https://example.com", None);
+            url.redirect = Some(status);
+            assert!(url.validate().is_ok());
+            assert_eq!(url.redirect().unwrap().status(), status);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_other_status_codes() {
+        let mut url = make_url("https://example.com", None);
+        url.redirect = Some(200);
+        let err = url.validate().unwrap_err();
+        assert_eq!(err.to_string(), "invalid redirect status: 200");
+    }
+
+    #[test]
+    fn forwarding_defaults_to_false() {
+        let url: Url = serde_json::from_str(r#"{"url":"https://example.com"}"#).unwrap();
+        assert!(!url.forward_query());
+        assert!(!url.forward_path());
+
+        let url: Url = serde_json::from_str(
+            r#"{"url":"https://example.com","forward_query":true,"forward_path":true}"#,
+        )
+        .unwrap();
+        assert!(url.forward_query());
+        assert!(url.forward_path());
+    }
+
+    fn with_utm(url: &str, utm: &str) -> Url {
+        let mut u = make_url(url, None);
+        u.utm = Some(serde_json::from_str(utm).unwrap());
+        u
+    }
+
+    #[test]
+    fn destination_merges_utm() {
+        let url = with_utm(
+            "https://example.com/page?a=1",
+            r#"{"source":"mail","medium":"email","campaign":"spring sale"}"#,
+        );
+        assert!(url.validate().is_ok());
+        assert_eq!(
+            url.destination(),
+            "https://example.com/page?a=1&utm_source=mail&utm_medium=email&utm_campaign=spring+sale"
+        );
+    }
+
+    #[test]
+    fn destination_without_utm_is_url() {
+        let url = make_url("https://example.com", None);
+        assert_eq!(url.destination(), "https://example.com");
+    }
+
+    #[test]
+    fn validate_utm_requires_source() {
+        let url = with_utm("https://example.com", r#"{"campaign":"x"}"#);
+        let err = url.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid UTM parameters: source is required"
+        );
+    }
+
+    #[test]
+    fn validate_utm_rejects_blank_and_long_values() {
+        let url = with_utm("https://example.com", r#"{"source":"x","term":"  "}"#);
+        assert!(url.validate().is_err());
+
+        let long = "a".repeat(UTM_MAX_LENGTH + 1);
+        let url = with_utm("https://example.com", &format!(r#"{{"source":"{long}"}}"#));
+        assert!(url.validate().is_err());
+    }
+
+    #[test]
+    fn password_is_hidden_from_debug() {
+        let url: Url =
+            serde_json::from_str(r#"{"url":"https://example.com","password":"hunter2"}"#).unwrap();
+        assert_eq!(url.password(), Some("hunter2"));
+        assert!(!format!("{:?}", url).contains("hunter2"));
+    }
+
+    #[test]
+    fn validate_rejects_empty_password() {
+        let url: Url =
+            serde_json::from_str(r#"{"url":"https://example.com","password":""}"#).unwrap();
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid password: must not be empty"
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_clicks() {
+        let mut url = make_url("https://example.com", None);
+        url.max_clicks = Some(0);
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "max_clicks must be positive"
+        );
+        url.max_clicks = Some(1);
+        assert!(url.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_window() {
+        let mut url = make_url("https://example.com", None);
+        url.not_before = Some("2026-01-01T00:00:00Z".to_string());
+        url.not_after = Some("2026-02-01T00:00:00+01:00".to_string());
+        assert!(url.validate().is_ok());
+        assert_eq!(
+            url.not_before().unwrap().timestamp_millis(),
+            1_767_225_600_000
+        );
+        assert!(url.not_after().is_some());
+
+        std::mem::swap(&mut url.not_before, &mut url.not_after);
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "not_before must not be after not_after"
+        );
+    }
+
+    #[test]
+    fn validate_rejects_bad_timestamp() {
+        let mut url = make_url("https://example.com", None);
+        url.not_after = Some("tomorrow".to_string());
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid not_after: expected an RFC 3339 timestamp"
+        );
+    }
+
+    #[test]
+    fn validate_fallback() {
+        let mut url = make_url("https://example.com", None);
+        url.fallback = Some("not a url".to_string());
+        assert!(url.validate().is_err());
+        url.fallback = Some("https://example.com/over".to_string());
+        assert!(url.validate().is_ok());
+    }
+
+    fn with_targets(targets: &str) -> Url {
+        let mut url = make_url("https://example.com", None);
+        url.targets = serde_json::from_str(targets).unwrap();
+        url
+    }
+
+    #[test]
+    fn validate_targets_accepts_weighted_split() {
+        let url = with_targets(
+            r#"[{"url":"https://a.com","weight":70},{"url":"https://b.com","weight":30}]"#,
+        );
+        assert!(url.validate().is_ok());
+        assert_eq!(url.targets().len(), 2);
+    }
+
+    #[test]
+    fn validate_targets_rejects_bad_input() {
+        let url = with_targets(r#"[{"url":"nope","weight":1}]"#);
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid targets: target URL is invalid"
+        );
+
+        let url = with_targets(r#"[{"url":"https://a.com","weight":0}]"#);
+        assert!(url.validate().is_err());
+
+        let url = with_targets(
+            r#"[{"url":"https://a.com","weight":4294967295},{"url":"https://b.com","weight":1}]"#,
+        );
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid targets: weights sum too large"
+        );
+
+        let many = vec![r#"{"url":"https://a.com","weight":1}"#; MAX_TARGETS + 1].join(",");
+        let url = with_targets(&format!("[{many}]"));
+        assert!(url.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rules() {
+        let mut url = make_url("https://example.com", None);
+        url.rules = serde_json::from_str(
+            r#"[{"os":"ios","url":"https://apps.apple.com"},{"device":"desktop","url":"https://example.com/web"}]"#,
+        )
+        .unwrap();
+        assert!(url.validate().is_ok());
+
+        url.rules = serde_json::from_str(r#"[{"bot":true,"url":"nope"}]"#).unwrap();
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid rules: rule URL is invalid"
+        );
+
+        url.rules =
+            serde_json::from_str(r#"[{"countries":["DEU"],"url":"https://example.de"}]"#).unwrap();
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid rules: country must be a two-letter code"
+        );
+    }
+
+    #[test]
+    fn validate_rejects_preview_suffix() {
+        let url = make_url("https://example.com", Some("abc+"));
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid name: must not end with '+'"
+        );
+        assert!(
+            make_url("https://example.com", Some("a+b"))
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn validate_name_charset() {
+        for name in ["docs", "Q3-launch_v1.2~x"] {
+            assert!(
+                make_url("https://example.com", Some(name))
+                    .validate()
+                    .is_ok()
+            );
+        }
+        for name in ["", "a b", "a/b", "caf\u{e9}", "a?b", "50%"] {
+            let url = make_url("https://example.com", Some(name));
+            assert_eq!(
+                url.validate().unwrap_err().code(),
+                "invalid_name",
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_rejects_reserved_names() {
+        for name in ["openapi.json", "usage", "campaigns"] {
+            let url = make_url("https://example.com", Some(name));
+            assert_eq!(
+                url.validate().unwrap_err().to_string(),
+                "invalid name: reserved for the API"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_tags() {
+        let mut url = make_url("https://example.com", None);
+        url.tags = vec!["launch".to_string(), "q3".to_string()];
+        assert!(url.validate().is_ok());
+        url.tags.push(" ".to_string());
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid tags: tags must be 1 to 64 characters"
+        );
+        url.tags = vec!["t".to_string(); 33];
+        assert!(url.validate().is_err());
+    }
+
+    #[test]
+    fn validate_open_graph() {
+        let mut url = make_url("https://example.com", None);
+        url.title = Some("Launch day".to_string());
+        url.image = Some("https://example.com/card.png".to_string());
+        assert!(url.validate().is_ok());
+
+        url.image = Some("ftp://example.com/card.png".to_string());
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid Open Graph metadata: image must be an http(s) URL"
+        );
+
+        url.image = None;
+        url.title = Some("x".repeat(300));
+        assert!(url.validate().is_err());
+    }
+
+    #[test]
+    fn validate_languages() {
+        let mut url = make_url("https://example.com", None);
+        url.languages = serde_json::from_str(
+            r#"[{"language":"de","url":"https://example.de"},{"language":"zh-Hant-TW","url":"https://example.tw"}]"#,
+        )
+        .unwrap();
+        assert!(url.validate().is_ok());
+
+        url.languages =
+            serde_json::from_str(r#"[{"language":"en_US","url":"https://example.com"}]"#).unwrap();
+        assert_eq!(
+            url.validate().unwrap_err().to_string(),
+            "invalid languages: language must be a BCP 47 tag"
+        );
+    }
+
+    #[test]
+    fn qr_defaults() {
+        let q = Qr::default();
+        assert!(q.validate().is_ok());
+        assert_eq!(q.format(), qr::Format::Png);
+        assert_eq!(q.style(), qr::Style::default());
+    }
+
+    #[test]
+    fn qr_options() {
+        let q = Qr {
+            format: qr::Format::Svg,
+            size: Some(512),
+            ecc: Some(qr::Level::H),
+            margin: Some(0),
+            fg: Some("#336699".to_string()),
+            bg: Some("eee".to_string()),
+        };
+        assert!(q.validate().is_ok());
+        let style = q.style();
+        assert_eq!(style.size, 512);
+        assert_eq!(style.level, qr::Level::H);
+        assert_eq!(style.margin, 0);
+        assert_eq!(style.foreground, qr::Rgb::parse("336699").unwrap());
+    }
+
+    #[test]
+    fn qr_rejects_bad_options() {
+        let q = Qr {
+            size: Some(10_000),
+            ..Qr::default()
+        };
+        assert!(q.validate().is_err());
+        let q = Qr {
+            margin: Some(100),
+            ..Qr::default()
+        };
+        assert!(q.validate().is_err());
+        let q = Qr {
+            fg: Some("#000000".to_string()),
+            bg: Some("red".to_string()),
+            ..Qr::default()
+        };
+        let err = q.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid QR options: colours must be hex codes like #1a2b3c"
+        );
+        assert_eq!(err.field(), "bg");
+    }
+
+    #[test]
+    fn validation_error_code_and_field() {
+        let mut url = make_url("https://example.com", None);
+        url.not_after = Some(String::from("tomorrow"));
+        let err = url.validate().unwrap_err();
+        assert_eq!(err.code(), "invalid_timestamp");
+        assert_eq!(err.field(), "not_after");
+
+        let err = make_url("invalid", None).validate().unwrap_err();
+        assert_eq!((err.code(), err.field()), ("invalid_url", "url"));
+    }
+
     #[test]
     fn validation_error_display() {
         let url = make_url("invalid", None);
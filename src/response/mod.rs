@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::model;
+
+mod problem;
+
+pub use problem::{Problem, rejected};
+
+// Most of these structs are only sent to clients, so `Serialize` suffices.
+// Those the `fesghel-client` SDK reads back also derive `Deserialize`.
+// `ToSchema` describes them in the OpenAPI document, see `handler::docs`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Quota {
+    used: u64,
+    // `None` serializes as `null`, meaning the quota is unlimited.
+    limit: Option<u64>,
+}
+
+impl Quota {
+    // A configured limit of zero means unlimited.
+    pub fn new(used: u64, limit: u64) -> Self {
+        Quota {
+            used,
+            limit: (limit > 0).then_some(limit),
+        }
+    }
+
+    // Whether one more item would go over the limit.
+    pub fn exhausted(&self) -> bool {
+        self.limit.is_some_and(|limit| self.used >= limit)
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Usage {
+    links: Quota,
+    custom_keys: Quota,
+}
+
+impl Usage {
+    pub fn new(links: Quota, custom_keys: Quota) -> Self {
+        Usage { links, custom_keys }
+    }
+
+    /// Count one more link, and one more custom key when `custom` is set.
+    /// Nothing is counted when a quota would be exceeded; the error names it.
+    pub fn reserve(&mut self, custom: bool) -> Result<(), &'static str> {
+        if self.links.exhausted() {
+            return Err("links");
+        }
+        if custom && self.custom_keys.exhausted() {
+            return Err("custom keys");
+        }
+        self.links.used += 1;
+        if custom {
+            self.custom_keys.used += 1;
+        }
+        Ok(())
+    }
+}
+
+// Outcome of one item of a batch create, in request order.
+// Exactly one of `key` and `error` is set; `error` is the problem document a
+// single create would have answered with.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Created {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Problem>,
+}
+
+impl Created {
+    pub fn key(key: &str) -> Self {
+        Created {
+            key: Some(key.to_string()),
+            error: None,
+        }
+    }
+
+    // `impl Into<Problem>` accepts problems as well as validation and store errors.
+    pub fn error(error: impl Into<Problem>) -> Self {
+        Created {
+            key: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+// Number of links tagged with one UTM campaign.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Campaign {
+    campaign: String,
+    links: u64,
+}
+
+impl Campaign {
+    pub fn new(campaign: String, links: u64) -> Self {
+        Campaign { campaign, links }
+    }
+}
+
+// Public view of a stored link - internal fields such as the owner stay hidden.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Link {
+    key: String,
+    url: String,
+}
+
+impl Link {
+    pub fn new(url: &model::Url) -> Self {
+        Link {
+            key: url.key().to_string(),
+            url: url.url().to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+// JSON has no date type; timestamps are sent as RFC 3339 strings.
+fn timestamp(at: Option<DateTime>) -> Option<String> {
+    at.and_then(|at| at.try_to_rfc3339_string().ok())
+}
+
+// A link just created. `url` is the destination as stored, with UTM
+// parameters merged in, and `short_url` the absolute URL to hand out.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Shortened {
+    key: String,
+    short_url: String,
+    url: String,
+    created_at: Option<String>,
+    // End of the activation window, if the link has one.
+    expires_at: Option<String>,
+}
+
+impl Shortened {
+    pub fn new(url: &model::Url, short_url: String) -> Self {
+        Shortened {
+            key: url.key().to_string(),
+            short_url,
+            url: url.url().to_string(),
+            created_at: timestamp(url.created_at()),
+            expires_at: timestamp(url.not_after()),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn short_url(&self) -> &str {
+        &self.short_url
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
+
+    pub fn expires_at(&self) -> Option<&str> {
+        self.expires_at.as_deref()
+    }
+}
+
+// Settings of a link as shown to its owner. The password itself never
+// leaves the server, only whether there is one.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Details {
+    key: String,
+    url: String,
+    created_at: Option<String>,
+    redirect: Option<u16>,
+    // Clicks left on a click-limited link.
+    max_clicks: Option<i64>,
+    not_before: Option<String>,
+    not_after: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    protected: bool,
+}
+
+impl Details {
+    pub fn new(url: &model::Url) -> Self {
+        Details {
+            key: url.key().to_string(),
+            url: url.url().to_string(),
+            created_at: timestamp(url.created_at()),
+            redirect: url.redirect().map(|r| r.status()),
+            max_clicks: url.max_clicks(),
+            not_before: timestamp(url.not_before()),
+            not_after: timestamp(url.not_after()),
+            title: url.title().map(String::from),
+            description: url.description().map(String::from),
+            image: url.image().map(String::from),
+            tags: url.tags().to_vec(),
+            protected: url.password().is_some(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
+
+    pub fn redirect(&self) -> Option<u16> {
+        self.redirect
+    }
+
+    pub fn max_clicks(&self) -> Option<i64> {
+        self.max_clicks
+    }
+
+    pub fn not_before(&self) -> Option<&str> {
+        self.not_before.as_deref()
+    }
+
+    pub fn not_after(&self) -> Option<&str> {
+        self.not_after.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn protected(&self) -> bool {
+        self.protected
+    }
+}
+
+// Click totals of one link.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Stats {
+    key: String,
+    clicks: u64,
+    // Clicks per ISO country code; clicks from unknown countries only count
+    // towards `clicks`. A `BTreeMap` keeps the countries sorted.
+    countries: BTreeMap<String, u64>,
+}
+
+impl Stats {
+    pub fn new(key: &str, by_country: Vec<(Option<String>, u64)>) -> Self {
+        let clicks = by_country.iter().map(|(_, n)| n).sum();
+        let countries = by_country
+            .into_iter()
+            .filter_map(|(country, n)| Some((country?, n)))
+            .collect();
+        Stats {
+            key: key.to_string(),
+            clicks,
+            countries,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn clicks(&self) -> u64 {
+        self.clicks
+    }
+
+    pub fn countries(&self) -> &BTreeMap<String, u64> {
+        &self.countries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_limit_is_unlimited() {
+        let q = Quota::new(1_000_000, 0);
+        assert!(!q.exhausted());
+        let json = serde_json::to_string(&q).unwrap();
+        assert_eq!(json, r#"{"used":1000000,"limit":null}"#);
+    }
+
+    #[test]
+    fn exhausted_at_limit() {
+        assert!(!Quota::new(4, 5).exhausted());
+        assert!(Quota::new(5, 5).exhausted());
+    }
+
+    #[test]
+    fn link_hides_owner() {
+        let url = crate::model::Url::new("https://example.com", "k").with_owner("key:secret");
+        let json = serde_json::to_string(&Link::new(&url)).unwrap();
+        assert_eq!(json, r#"{"key":"k","url":"https://example.com"}"#);
+    }
+
+    #[test]
+    fn details_hide_the_password() {
+        let url = model::Url::new("https://example.com", "k")
+            .with_owner("key:secret")
+            .with_password(Some("hunter2"));
+        let json = serde_json::to_value(Details::new(&url)).unwrap();
+        assert_eq!(json["protected"], true);
+        assert!(json.get("password").is_none());
+        assert!(json.get("owner").is_none());
+    }
+
+    #[test]
+    fn shortened_carries_the_expiry() {
+        let not_after = DateTime::parse_rfc3339_str("2100-01-01T00:00:00Z").unwrap();
+        let url = model::Url::new("https://example.com", "k").with_window(None, Some(not_after));
+        let created = Shortened::new(&url, String::from("https://sho.rt/api/k"));
+        assert_eq!(created.short_url(), "https://sho.rt/api/k");
+        assert_eq!(created.expires_at(), Some("2100-01-01T00:00:00Z"));
+        assert_eq!(created.created_at(), None);
+    }
+
+    #[test]
+    fn stats_sum_all_countries() {
+        let stats = Stats::new("k", vec![(Some("DE".to_string()), 3), (None, 2)]);
+        assert_eq!(stats.clicks(), 5);
+        assert_eq!(stats.countries().len(), 1);
+        assert_eq!(stats.countries()["DE"], 3);
+    }
+
+    #[test]
+    fn reserve_counts_until_exhausted() {
+        let mut u = Usage::new(Quota::new(0, 2), Quota::new(0, 1));
+        assert_eq!(u.reserve(true), Ok(()));
+        assert_eq!(u.reserve(true), Err("custom keys"));
+        assert_eq!(u.reserve(false), Ok(()));
+        assert_eq!(u.reserve(false), Err("links"));
+    }
+
+    #[test]
+    fn created_serializes_one_field() {
+        let ok = serde_json::to_string(&Created::key("abc")).unwrap();
+        assert_eq!(ok, r#"{"key":"abc"}"#);
+        let err = crate::request::ValidationError::InvalidTags("too many tags");
+        let failed = serde_json::to_value(Created::error(&err)).unwrap();
+        assert!(failed.get("key").is_none());
+        assert_eq!(failed["error"]["code"], "invalid_tags");
+        assert_eq!(failed["error"]["field"], "tags");
+        assert_eq!(failed["error"]["status"], 400);
+    }
+
+    #[test]
+    fn usage_serializes_both_quotas() {
+        let u = Usage::new(Quota::new(1, 10), Quota::new(0, 2));
+        let json = serde_json::to_string(&u).unwrap();
+        assert!(json.contains(r#""links":{"used":1,"limit":10}"#));
+        assert!(json.contains(r#""custom_keys":{"used":0,"limit":2}"#));
+    }
+}
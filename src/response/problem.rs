@@ -0,0 +1,230 @@
+// Error bodies in the `application/problem+json` format of RFC 7807.
+// Every error the API answers with is a `Problem`:
+//
+//   {
+//     "type": "about:blank",
+//     "title": "Bad Request",
+//     "status": 400,
+//     "detail": "invalid URL: relative URL without a base",
+//     "code": "invalid_url",
+//     "field": "url"
+//   }
+//
+// With the `about:blank` type the title is the status phrase, so `code`,
+// an extension member, is what clients match on.
+
+use std::fmt;
+
+use actix_web::http::StatusCode;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::{HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::request::ValidationError;
+use crate::store;
+
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+const ABOUT_BLANK: &str = "about:blank";
+
+fn about_blank() -> String {
+    String::from(ABOUT_BLANK)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Problem {
+    // `type` is a keyword, so the field is renamed on the wire.
+    #[serde(rename = "type", default = "about_blank")]
+    kind: String,
+    title: String,
+    status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
+}
+
+impl Problem {
+    /// Problem with the given status and stable `code`, titled after the status.
+    pub fn new(status: StatusCode, code: &str) -> Self {
+        Problem {
+            kind: about_blank(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: None,
+            code: code.to_string(),
+            field: None,
+        }
+    }
+
+    /// Human-readable explanation of this occurrence.
+    pub fn with_detail(mut self, detail: impl ToString) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    /// Request field or query parameter the problem is about.
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    /// The generic answer to failures the client cannot do anything about.
+    /// Their cause is logged, not sent.
+    pub fn internal() -> Self {
+        Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "internal")
+            .with_detail("Something went wrong")
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.detail.as_deref().unwrap_or(&self.title))
+    }
+}
+
+// `ResponseError` lets actix turn the value into a response, either when a
+// handler returns it as an error or through an explicit `error_response()`.
+impl ResponseError for Problem {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .insert_header((header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE)))
+            .json(self)
+    }
+}
+
+impl From<&ValidationError> for Problem {
+    fn from(err: &ValidationError) -> Self {
+        Problem::new(StatusCode::BAD_REQUEST, err.code())
+            .with_detail(err)
+            .with_field(err.field())
+    }
+}
+
+impl ResponseError for ValidationError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from(self).error_response()
+    }
+}
+
+impl From<&store::Error> for Problem {
+    fn from(err: &store::Error) -> Self {
+        match err {
+            store::Error::DuplicateKey(_) => Problem::new(StatusCode::CONFLICT, "duplicate_key")
+                .with_detail(err)
+                .with_field("name"),
+            // Database errors may mention hosts or queries.
+            store::Error::Database(_) => Problem::internal(),
+        }
+    }
+}
+
+impl ResponseError for store::Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            store::Error::DuplicateKey(_) => StatusCode::CONFLICT,
+            store::Error::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from(self).error_response()
+    }
+}
+
+/// Problem for a request its extractors could not read, e.g. a malformed
+/// JSON body or a query parameter of the wrong type. Used as the error
+/// handler of actix's extractor configs, which answer in plain text otherwise;
+/// the status is the one actix would have used.
+pub fn rejected(code: &str, err: impl ResponseError) -> actix_web::Error {
+    Problem::new(err.status_code(), code)
+        .with_detail(&err)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+
+    #[test]
+    fn validation_error_names_the_field() {
+        let err = ValidationError::InvalidTags("too many tags");
+        let problem = Problem::from(&err);
+        assert_eq!(problem.status(), 400);
+        assert_eq!(problem.title(), "Bad Request");
+        assert_eq!(problem.code(), "invalid_tags");
+        assert_eq!(problem.field(), Some("tags"));
+        assert_eq!(problem.detail(), Some("invalid tags: too many tags"));
+    }
+
+    #[test]
+    fn rejected_requests_keep_their_status() {
+        let err = rejected(
+            "invalid_body",
+            actix_web::error::JsonPayloadError::Overflow { limit: 10 },
+        );
+        let response = err.error_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE
+        );
+    }
+
+    #[test]
+    fn database_errors_are_not_leaked() {
+        let err = store::Error::Database(Box::new(std::io::Error::other("db.internal:27017")));
+        let response = err.error_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE
+        );
+
+        let body = response.into_body().try_into_bytes().unwrap();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem, Problem::internal());
+        assert!(!String::from_utf8_lossy(&body).contains("27017"));
+    }
+
+    #[test]
+    fn serializes_the_type_member() {
+        let problem = Problem::new(StatusCode::CONFLICT, "duplicate_key");
+        let json = serde_json::to_value(&problem).unwrap();
+        assert_eq!(json["type"], "about:blank");
+        assert_eq!(json["title"], "Conflict");
+        // Absent members are left out rather than sent as `null`.
+        assert!(json.get("field").is_none());
+    }
+}
@@ -0,0 +1,382 @@
+// Entry point for running the shortener inside another actix-web application.
+// A `Builder` collects the stores, settings and metrics registry; the
+// `Service` it builds hands out the routes for each worker:
+//
+//   let service = fesghel::Builder::connect(settings.database())
+//       .await
+//       .with_settings(&settings)?
+//       .with_metrics(&registry)
+//       .build();
+//   HttpServer::new(move || {
+//       App::new().service(web::scope("/links").configure(|cfg| service.configure(cfg)))
+//   })
+
+use std::fmt;
+
+use actix_web::{Scope, web};
+use prometheus::Registry;
+
+use crate::client;
+use crate::database;
+use crate::geoip;
+use crate::handler;
+use crate::metrics;
+use crate::model;
+use crate::ratelimit;
+use crate::response;
+use crate::setting::{self, Settings};
+use crate::store;
+
+// Path of the API scope, relative to wherever the service is mounted.
+pub(crate) const API_SCOPE: &str = "/api";
+
+// Settings that cannot be turned into a working service.
+#[derive(Debug)]
+pub enum Error {
+    Redirect(u16),
+    Proxies(ipnet::AddrParseError),
+    GeoIp(maxminddb::MaxMindDbError),
+    DocsAssets(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Redirect(status) => write!(
+                f,
+                "redirect status must be one of 301, 302, 307 or 308, not {}",
+                status
+            ),
+            Error::Proxies(err) => write!(
+                f,
+                "trusted proxies must be IP addresses or CIDR ranges: {}",
+                err
+            ),
+            Error::GeoIp(err) => write!(f, "loading GeoIP database failed: {}", err),
+            Error::DocsAssets(err) => write!(f, "loading Swagger UI assets failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Everything the handlers need. Cloning is cheap and shares the stores,
+/// rate limiters and GeoIP database, so all workers see the same state.
+#[derive(Clone)]
+pub struct Service {
+    urls: store::Url,
+    clicks: store::Click,
+    create_limit: ratelimit::Limiter,
+    redirect_limit: ratelimit::Limiter,
+    password_limit: ratelimit::Limiter,
+    quota: setting::Quota,
+    redirect: model::Redirect,
+    max_age: u64,
+    preview: bool,
+    proxies: client::Proxies,
+    api_keys: client::ApiKeys,
+    geoip: geoip::GeoIp,
+    public_url: Option<String>,
+    admin_token: Option<String>,
+    docs_assets: Option<web::Data<handler::docs::Assets>>,
+}
+
+pub struct Builder {
+    service: Service,
+}
+
+impl Builder {
+    /// Serve links from these stores with the handlers' defaults: no rate
+    /// limits or quotas, 307 redirects and admin endpoints disabled.
+    pub fn new(urls: store::Url, clicks: store::Click) -> Self {
+        Builder {
+            service: Service {
+                urls,
+                clicks,
+                create_limit: ratelimit::Limiter::unlimited(),
+                redirect_limit: ratelimit::Limiter::unlimited(),
+                password_limit: ratelimit::Limiter::unlimited(),
+                quota: setting::Quota::default(),
+                redirect: model::Redirect::Temporary,
+                max_age: 0,
+                preview: false,
+                proxies: client::Proxies::default(),
+                api_keys: client::ApiKeys::default(),
+                geoip: geoip::GeoIp::default(),
+                public_url: None,
+                admin_token: None,
+                docs_assets: None,
+            },
+        }
+    }
+
+    /// Connect to the configured database and open the stores, which also
+    /// creates their indexes.
+    pub async fn connect(cfg: &setting::Database) -> Self {
+        let db = database::connect(cfg).await;
+        Builder::new(
+            store::Url::new(db.clone()).await,
+            store::Click::new(db).await,
+        )
+    }
+
+    /// Apply every setting except the listen address and the database.
+    /// Fails on a redirect status, proxy list or GeoIP database that cannot
+    /// be used, so misconfiguration shows at startup rather than on first use.
+    pub fn with_settings(mut self, settings: &Settings) -> Result<Self, Error> {
+        let service = &mut self.service;
+
+        service.create_limit = ratelimit::Limiter::new(settings.ratelimit().create());
+        service.redirect_limit = ratelimit::Limiter::new(settings.ratelimit().redirect());
+        service.password_limit = ratelimit::Limiter::new(settings.ratelimit().password());
+        service.quota = settings.quota().clone();
+        let status = settings.redirect().status();
+        service.redirect =
+            model::Redirect::try_from(status).map_err(|_| Error::Redirect(status))?;
+        service.max_age = settings.redirect().max_age();
+        service.preview = settings.redirect().preview();
+        service.proxies =
+            client::Proxies::new(settings.server().trusted_proxies()).map_err(Error::Proxies)?;
+        service.api_keys = client::ApiKeys::from_settings(settings.api());
+        service.geoip = geoip::GeoIp::open(settings.geoip().database()).map_err(Error::GeoIp)?;
+        service.public_url = settings.server().public_url().map(String::from);
+        service.admin_token = settings.admin().token().map(String::from);
+        service.docs_assets = settings
+            .docs()
+            .assets()
+            .map(|dir| handler::docs::Assets::load(std::path::Path::new(dir)))
+            .transpose()
+            .map_err(Error::DocsAssets)?
+            .map(web::Data::new);
+
+        Ok(self)
+    }
+
+    /// Identify callers carrying one of these API keys by the key's name,
+    /// without loading the rest of the settings.
+    pub fn with_api_keys(mut self, api_keys: client::ApiKeys) -> Self {
+        self.service.api_keys = api_keys;
+        self
+    }
+
+    /// Register fesghel's metrics with `registry`. They are counted either
+    /// way, but only exported from a registry they are registered with.
+    pub fn with_metrics(self, registry: &Registry) -> Self {
+        metrics::register_metrics(registry);
+        self
+    }
+
+    pub fn build(self) -> Service {
+        self.service
+    }
+}
+
+impl Service {
+    /// Links, redirects and the admin endpoints under `/api`.
+    pub fn api(&self) -> Scope {
+        let links = handler::url::State::new(self.urls.clone(), self.clicks.clone())
+            .with_rate_limits(self.create_limit.clone(), self.redirect_limit.clone())
+            .with_password_limit(self.password_limit.clone())
+            .with_quota(self.quota.clone())
+            .with_redirect(self.redirect, self.max_age)
+            .with_preview(self.preview)
+            .with_proxies(self.proxies.clone())
+            .with_api_keys(self.api_keys.clone())
+            .with_geoip(self.geoip.clone())
+            .with_public_url(self.public_url.as_deref());
+        let admin = handler::admin::State::new(
+            self.urls.clone(),
+            self.clicks.clone(),
+            self.admin_token.as_deref(),
+        );
+
+        // Requests the extractors reject get problem documents like every
+        // other error, instead of actix's plain-text 400.
+        let scope = web::scope(API_SCOPE)
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _| response::rejected("invalid_body", err)),
+            )
+            .app_data(
+                web::FormConfig::default()
+                    .error_handler(|err, _| response::rejected("invalid_body", err)),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|err, _| response::rejected("invalid_query", err)),
+            )
+            .app_data(
+                web::PathConfig::default()
+                    .error_handler(|err, _| response::rejected("invalid_path", err)),
+            );
+
+        // Admin routes and the OpenAPI document are registered first, or
+        // `/{name}` would swallow them.
+        let scope = handler::docs::register(scope);
+        handler::url::register(links, handler::admin::register(admin, scope))
+    }
+
+    /// The health check at `/healthz`.
+    pub fn health(&self) -> Scope {
+        handler::healthz::register(web::scope("/healthz"))
+    }
+
+    /// Interactive API documentation at `/docs`.
+    pub fn docs(&self) -> Scope {
+        handler::docs::register_page(web::scope("/docs"), self.docs_assets.clone())
+    }
+
+    /// Register all scopes on an app or a scope of it, for use with
+    /// `App::configure` and `Scope::configure`.
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(self.api())
+            .service(self.health())
+            .service(self.docs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::{App, HttpResponse, test};
+
+    // Stores on an address nothing listens on; index creation gives up
+    // quickly and routes that do not touch the database still work.
+    async fn service() -> Service {
+        let db =
+            mongodb::Client::with_uri_str("mongodb://127.0.0.1:9/?serverSelectionTimeoutMS=50")
+                .await
+                .unwrap()
+                .database("fesghel_test");
+        Builder::new(
+            store::Url::new(db.clone()).await,
+            store::Click::new(db).await,
+        )
+        .build()
+    }
+
+    #[actix_web::test]
+    async fn mounts_inside_a_scope_of_the_host_app() {
+        let service = service().await;
+        let app = test::init_service(
+            App::new()
+                .service(web::scope("/links").configure(|cfg| service.configure(cfg)))
+                .route("/own", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let healthz = test::TestRequest::get().uri("/links/healthz").to_request();
+        assert_eq!(
+            test::call_service(&app, healthz).await.status(),
+            StatusCode::NO_CONTENT
+        );
+        // The host app's own routes are not shadowed.
+        let own = test::TestRequest::get().uri("/own").to_request();
+        assert_eq!(test::call_service(&app, own).await.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn serves_the_openapi_document() {
+        let service = service().await;
+        let app = test::init_service(App::new().configure(|cfg| service.configure(cfg))).await;
+
+        let spec = test::TestRequest::get()
+            .uri("/api/openapi.json")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, spec).await;
+        assert!(body["paths"]["/api/urls"]["post"].is_object());
+
+        let docs = test::TestRequest::get().uri("/docs").to_request();
+        assert_eq!(
+            test::call_service(&app, docs).await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[actix_web::test]
+    async fn errors_are_problem_documents() {
+        let service = service().await;
+        let app = test::init_service(App::new().configure(|cfg| service.configure(cfg))).await;
+
+        let create = test::TestRequest::post()
+            .uri("/api/urls")
+            .set_json(serde_json::json!({ "url": "not a url" }))
+            .to_request();
+        let resp = test::call_service(&app, create).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/problem+json"
+        );
+        let problem: crate::response::Problem = test::read_body_json(resp).await;
+        assert_eq!(problem.code(), "invalid_url");
+        assert_eq!(problem.field(), Some("url"));
+    }
+
+    #[actix_web::test]
+    async fn unreadable_requests_are_problem_documents() {
+        let service = service().await;
+        let app = test::init_service(App::new().configure(|cfg| service.configure(cfg))).await;
+
+        let malformed = test::TestRequest::post()
+            .uri("/api/urls")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{\"url\": ")
+            .to_request();
+        let resp = test::call_service(&app, malformed).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/problem+json"
+        );
+        let problem: crate::response::Problem = test::read_body_json(resp).await;
+        assert_eq!(problem.code(), "invalid_body");
+
+        let gif = test::TestRequest::get()
+            .uri("/api/urls/docs/qr?format=gif")
+            .to_request();
+        let resp = test::call_service(&app, gif).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let problem: crate::response::Problem = test::read_body_json(resp).await;
+        assert_eq!(problem.code(), "invalid_query");
+    }
+
+    #[actix_web::test]
+    async fn managing_links_needs_an_api_key() {
+        let service = service().await;
+        let app = test::init_service(App::new().configure(|cfg| service.configure(cfg))).await;
+
+        for (method, uri) in [
+            (Method::GET, "/api/urls"),
+            (Method::GET, "/api/urls/docs"),
+            (Method::DELETE, "/api/urls/docs"),
+            (Method::GET, "/api/urls/docs/stats"),
+            (Method::GET, "/api/urls/campaigns"),
+            (Method::GET, "/api/urls/campaigns/launch"),
+        ] {
+            let req = test::TestRequest::default()
+                .method(method)
+                .uri(uri)
+                // A made-up key is no better than none.
+                .insert_header(("X-API-Key", "made-up"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{uri}");
+            let problem: crate::response::Problem = test::read_body_json(resp).await;
+            assert_eq!(problem.code(), "unauthorized");
+        }
+    }
+
+    #[actix_web::test]
+    async fn admin_endpoints_are_disabled_by_default() {
+        let service = service().await;
+        let app = test::init_service(App::new().configure(|cfg| service.configure(cfg))).await;
+
+        let export = test::TestRequest::get().uri("/api/export").to_request();
+        assert_eq!(
+            test::call_service(&app, export).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -14,6 +14,93 @@ pub struct Server {
     // `u32` is unsigned 32-bit integer. Rust has explicit integer sizes:
     // i8/u8, i16/u16, i32/u32, i64/u64, i128/u128, isize/usize.
     port: u32,
+    // Reverse proxies (addresses or CIDR ranges) whose `X-Forwarded-For` is trusted.
+    // `#[serde(default)]` yields an empty list when the key is missing.
+    #[serde(default)]
+    trusted_proxies: Vec<String>,
+    // Public base URL of short links, e.g. "https://sho.rt".
+    // Derived from the request's `Host` header when unset.
+    public_url: Option<String>,
+    // Path a host application mounts the service under, e.g. "/links".
+    // Only the command line needs it; the server resolves its own routes.
+    mount_path: Option<String>,
+}
+
+// Credentials for the admin endpoints (bulk import and export).
+// `Default` lets configs without an `[admin]` section keep working.
+#[derive(Debug, Default, Deserialize)]
+pub struct Admin {
+    // Bearer token; the admin endpoints reject everyone while it is unset.
+    token: Option<String>,
+}
+
+// An issued API key. Requests carrying it are accounted to `name`, which is
+// also recorded as the owner of the links they create.
+#[derive(Debug, Deserialize)]
+pub struct ApiKey {
+    name: String,
+    key: String,
+}
+
+// Issued API keys. Without any, every client is identified by its address.
+#[derive(Debug, Default, Deserialize)]
+pub struct Api {
+    #[serde(default)]
+    keys: Vec<ApiKey>,
+}
+
+// The interactive API documentation page.
+#[derive(Debug, Default, Deserialize)]
+pub struct Docs {
+    // Directory holding `swagger-ui.css` and `swagger-ui-bundle.js` from the
+    // `swagger-ui-dist` package, served by the service itself. The page loads
+    // them from the unpkg CDN while it is unset.
+    assets: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeoIp {
+    // Path to a MaxMind-format (`.mmdb`) country or city database.
+    // Geo lookups are disabled when unset.
+    database: Option<String>,
+}
+
+// Token bucket parameters for one class of routes.
+#[derive(Debug, Deserialize)]
+pub struct Limit {
+    // Burst size. Zero disables limiting.
+    capacity: u32,
+    // Tokens added per second.
+    refill: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RateLimit {
+    create: Limit,
+    redirect: Limit,
+    // Failed password attempts, keyed by link instead of client.
+    password: Limit,
+}
+
+// Hard limits per owner, i.e. per issued API key or client address.
+// Zero means unlimited.
+// `Clone` lets each worker's handler state keep its own copy,
+// `Default` gives the unlimited configuration.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Quota {
+    max_links: u64,
+    max_custom_keys: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Redirect {
+    // Default status code for links without their own (301, 302, 307, 308).
+    status: u16,
+    // Seconds browsers and CDNs may cache permanent redirects.
+    max_age: u64,
+    // Serve the preview page for every link instead of redirecting.
+    #[serde(default)]
+    preview: bool,
 }
 
 // Composition: Settings contains other structs as fields.
@@ -22,6 +109,16 @@ pub struct Server {
 pub struct Settings {
     server: Server,
     database: Database,
+    ratelimit: RateLimit,
+    quota: Quota,
+    redirect: Redirect,
+    geoip: GeoIp,
+    #[serde(default)]
+    admin: Admin,
+    #[serde(default)]
+    api: Api,
+    #[serde(default)]
+    docs: Docs,
 }
 
 impl Settings {
@@ -57,6 +154,62 @@ impl Settings {
     pub fn database(&self) -> &Database {
         &self.database
     }
+
+    pub fn ratelimit(&self) -> &RateLimit {
+        &self.ratelimit
+    }
+
+    pub fn quota(&self) -> &Quota {
+        &self.quota
+    }
+
+    pub fn redirect(&self) -> &Redirect {
+        &self.redirect
+    }
+
+    pub fn geoip(&self) -> &GeoIp {
+        &self.geoip
+    }
+
+    pub fn admin(&self) -> &Admin {
+        &self.admin
+    }
+
+    pub fn api(&self) -> &Api {
+        &self.api
+    }
+
+    pub fn docs(&self) -> &Docs {
+        &self.docs
+    }
+}
+
+impl Api {
+    pub fn keys(&self) -> &[ApiKey] {
+        &self.keys
+    }
+}
+
+impl ApiKey {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+}
+
+impl Docs {
+    pub fn assets(&self) -> Option<&str> {
+        self.assets.as_deref()
+    }
+}
+
+impl Admin {
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
 }
 
 // Each struct gets its own impl block for its methods.
@@ -71,6 +224,26 @@ impl Server {
     pub fn host(&self) -> &str {
         self.host.as_str()
     }
+
+    // `&[String]` borrows the list as a slice.
+    pub fn trusted_proxies(&self) -> &[String] {
+        &self.trusted_proxies
+    }
+
+    pub fn public_url(&self) -> Option<&str> {
+        self.public_url.as_deref()
+    }
+
+    // Empty when the service is not mounted under a path.
+    pub fn mount_path(&self) -> &str {
+        self.mount_path.as_deref().unwrap_or_default()
+    }
+}
+
+impl GeoIp {
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
 }
 
 impl Database {
@@ -82,3 +255,51 @@ impl Database {
         self.name.as_str()
     }
 }
+
+impl RateLimit {
+    pub fn create(&self) -> &Limit {
+        &self.create
+    }
+
+    pub fn redirect(&self) -> &Limit {
+        &self.redirect
+    }
+
+    pub fn password(&self) -> &Limit {
+        &self.password
+    }
+}
+
+impl Limit {
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn refill(&self) -> f64 {
+        self.refill
+    }
+}
+
+impl Quota {
+    pub fn max_links(&self) -> u64 {
+        self.max_links
+    }
+
+    pub fn max_custom_keys(&self) -> u64 {
+        self.max_custom_keys
+    }
+}
+
+impl Redirect {
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn max_age(&self) -> u64 {
+        self.max_age
+    }
+
+    pub fn preview(&self) -> bool {
+        self.preview
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use mongodb::bson::doc;
+use mongodb::{Collection, Cursor, Database, IndexModel};
+use serde::Deserialize;
+
+use super::error::Error;
+use crate::metrics;
+use crate::model;
+
+const COLLECTION: &str = "clicks";
+
+// Append-only store of click events, kept apart from the links themselves so
+// heavy redirect traffic never contends with link documents.
+#[derive(Clone)]
+pub struct Click {
+    collection: Collection<model::Click>,
+}
+
+impl Click {
+    pub async fn new(db: Database) -> Self {
+        let collection = db.collection(COLLECTION);
+
+        // Clicks are always read per link and time range.
+        let _ = collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "key": 1, "at": 1 })
+                    .build(),
+            )
+            .await;
+
+        Click { collection }
+    }
+
+    /// Cursor over every click event, oldest first, for exports.
+    pub async fn all(&self) -> Result<Cursor<model::Click>, Error> {
+        let start = Instant::now();
+
+        let result = self
+            .collection
+            .find(doc! {})
+            .sort(doc! { "at": 1 })
+            .await
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        // Only opening the cursor is timed; batches are fetched as it is read.
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Remove every click on `key`, when its link is deleted. Returns how
+    /// many there were.
+    pub async fn delete_key(&self, key: &str) -> Result<u64, Error> {
+        let start = Instant::now();
+
+        let result = self
+            .collection
+            .delete_many(doc! { "key": key })
+            .await
+            .map(|deleted| deleted.deleted_count)
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        metrics::observe_db_write(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Clicks on `key` per visitor country; `None` counts the clicks whose
+    /// country is unknown.
+    pub async fn by_country(&self, key: &str) -> Result<Vec<(Option<String>, u64)>, Error> {
+        #[derive(Deserialize)]
+        struct Row {
+            #[serde(rename = "_id")]
+            country: Option<String>,
+            clicks: u64,
+        }
+
+        let start = Instant::now();
+
+        let pipeline = [
+            doc! { "$match": { "key": key } },
+            doc! { "$group": { "_id": "$country", "clicks": { "$sum": 1 } } },
+        ];
+        let result = async {
+            let mut cursor = self
+                .collection
+                .aggregate(pipeline)
+                .with_type::<Row>()
+                .await?;
+            let mut rows = Vec::new();
+            while cursor.advance().await? {
+                let row = cursor.deserialize_current()?;
+                rows.push((row.country, row.clicks));
+            }
+            Ok(rows)
+        }
+        .await
+        .map_err(|err: mongodb::error::Error| Error::Database(Box::new(err)));
+
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    pub async fn record(&self, click: &model::Click) -> Result<(), Error> {
+        let start = Instant::now();
+
+        let result = self
+            .collection
+            .insert_one(click)
+            .await
+            .map(|_| ())
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        metrics::observe_db_write(start.elapsed().as_secs_f64());
+
+        result
+    }
+}
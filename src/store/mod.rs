@@ -1,4 +1,7 @@
+mod click;
 mod error;
 mod url;
 
+pub use click::Click;
+pub use error::Error;
 pub use url::*;
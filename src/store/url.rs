@@ -2,8 +2,9 @@ use std::time::Instant;
 
 use mongodb::bson::doc;
 use mongodb::error::{ErrorKind, WriteFailure};
-use mongodb::{Collection, Database, IndexModel};
+use mongodb::{Collection, Cursor, Database, IndexModel};
 use rand::{RngExt, distr::Alphanumeric, rng};
+use serde::Deserialize;
 
 // `super::` refers to the parent module. Here it accesses `store::error`.
 use super::error::Error;
@@ -46,6 +47,20 @@ impl Url {
         // Index creation may fail if index already exists - that's OK.
         let _ = collection.create_index(index).await;
 
+        // Non-unique index backing the per-owner quota counts.
+        let _ = collection
+            .create_index(IndexModel::builder().keys(doc! { "owner": 1 }).build())
+            .await;
+
+        // Compound index for per-owner campaign reports.
+        let _ = collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "owner": 1, "utm.campaign": 1 })
+                    .build(),
+            )
+            .await;
+
         Url { collection }
     }
 
@@ -87,6 +102,271 @@ impl Url {
         result
     }
 
+    /// Count links created by `owner`, optionally only those with custom keys.
+    /// Expired links count too: one with a `fallback` keeps redirecting.
+    pub async fn count_owned(&self, owner: &str, custom_only: bool) -> Result<u64, Error> {
+        let start = Instant::now();
+
+        let mut filter = doc! { "owner": owner };
+        if custom_only {
+            filter.insert("custom", true);
+        }
+
+        let result = self
+            .collection
+            .count_documents(filter)
+            .await
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Number of links per UTM campaign for `owner`, sorted by campaign name.
+    pub async fn campaigns(&self, owner: &str) -> Result<Vec<(String, u64)>, Error> {
+        // Shape of each document produced by the `$group` stage below.
+        #[derive(Deserialize)]
+        struct Row {
+            #[serde(rename = "_id")]
+            campaign: String,
+            links: u64,
+        }
+
+        let start = Instant::now();
+
+        let pipeline = [
+            doc! { "$match": { "owner": owner, "utm.campaign": { "$exists": true } } },
+            doc! { "$group": { "_id": "$utm.campaign", "links": { "$sum": 1 } } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+
+        // An inner `async` block lets `?` short-circuit while the metric below
+        // is still recorded for failed reads.
+        let result = async {
+            let mut cursor = self
+                .collection
+                .aggregate(pipeline)
+                .with_type::<Row>()
+                .await?;
+            let mut rows = Vec::new();
+            // `advance()` fetches the next document; `deserialize_current()` decodes it.
+            while cursor.advance().await? {
+                let row = cursor.deserialize_current()?;
+                rows.push((row.campaign, row.links));
+            }
+            Ok(rows)
+        }
+        .await
+        .map_err(|err: mongodb::error::Error| Error::Database(Box::new(err)));
+
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Newest `limit` links of `owner` tagged with the given UTM campaign.
+    pub async fn by_campaign(
+        &self,
+        owner: &str,
+        campaign: &str,
+        limit: i64,
+    ) -> Result<Vec<model::Url>, Error> {
+        let start = Instant::now();
+
+        let result = async {
+            let mut cursor = self
+                .collection
+                .find(doc! { "owner": owner, "utm.campaign": campaign })
+                .sort(doc! { "_id": -1 })
+                .limit(limit)
+                .await?;
+            let mut urls = Vec::new();
+            while cursor.advance().await? {
+                urls.push(cursor.deserialize_current()?);
+            }
+            Ok(urls)
+        }
+        .await
+        .map_err(|err: mongodb::error::Error| Error::Database(Box::new(err)));
+
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Links stored under any of `keys`.
+    pub async fn find_keys(&self, keys: &[&str]) -> Result<Vec<model::Url>, Error> {
+        let start = Instant::now();
+
+        let result = async {
+            let mut cursor = self
+                .collection
+                .find(doc! { "key": { "$in": keys } })
+                .await?;
+            let mut urls = Vec::new();
+            while cursor.advance().await? {
+                urls.push(cursor.deserialize_current()?);
+            }
+            Ok(urls)
+        }
+        .await
+        .map_err(|err: mongodb::error::Error| Error::Database(Box::new(err)));
+
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Cursor over every link, for exports.
+    /// Documents are fetched in batches as the cursor is read, so the
+    /// collection is never loaded into memory.
+    pub async fn all(&self) -> Result<Cursor<model::Url>, Error> {
+        let start = Instant::now();
+
+        let result = self
+            .collection
+            .find(doc! {})
+            .await
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        // Only opening the cursor is timed; batches are fetched as it is read.
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Newest links first, optionally only those of `owner`.
+    /// `_id` is an `ObjectId`, which starts with its creation time, so it
+    /// orders links that predate `created_at` as well.
+    pub async fn list(&self, owner: Option<&str>, limit: i64) -> Result<Vec<model::Url>, Error> {
+        let start = Instant::now();
+
+        let filter = match owner {
+            Some(owner) => doc! { "owner": owner },
+            None => doc! {},
+        };
+        let result = async {
+            let mut cursor = self
+                .collection
+                .find(filter)
+                .sort(doc! { "_id": -1 })
+                .limit(limit)
+                .await?;
+            let mut urls = Vec::new();
+            while cursor.advance().await? {
+                urls.push(cursor.deserialize_current()?);
+            }
+            Ok(urls)
+        }
+        .await
+        .map_err(|err: mongodb::error::Error| Error::Database(Box::new(err)));
+
+        metrics::observe_db_read(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Remove a link. Returns `false` when there was none under `name`.
+    pub async fn delete(&self, name: &str) -> Result<bool, Error> {
+        let start = Instant::now();
+
+        let result = self
+            .collection
+            .delete_one(doc! { "key": name })
+            .await
+            .map(|deleted| deleted.deleted_count > 0)
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        metrics::observe_db_write(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Give links stored before `created_at` existed the creation time
+    /// embedded in their `ObjectId`. Returns how many were updated.
+    pub async fn backfill_created_at(&self) -> Result<u64, Error> {
+        let start = Instant::now();
+
+        // A pipeline update computes the new value from the document itself.
+        let result = self
+            .collection
+            .update_many(
+                doc! { "created_at": { "$exists": false } },
+                vec![doc! { "$set": { "created_at": { "$toDate": "$_id" } } }],
+            )
+            .await
+            .map(|updated| updated.modified_count)
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        metrics::observe_db_write(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Use up one click of a click-limited link.
+    /// Returns `false` once the link is exhausted. The filter and the decrement
+    /// run as one atomic `findAndModify`, so concurrent redirects can never
+    /// push the counter below zero.
+    pub async fn consume(&self, name: &str) -> Result<bool, Error> {
+        let start = Instant::now();
+
+        let result = self
+            .collection
+            .find_one_and_update(
+                doc! { "key": name, "max_clicks": { "$gt": 0 } },
+                doc! { "$inc": { "max_clicks": -1 } },
+            )
+            .await
+            .map(|doc| doc.is_some())
+            .map_err(|err| Error::Database(Box::new(err)));
+
+        metrics::observe_db_write(start.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Insert many links in one round trip.
+    /// The insert is unordered, so a failing document does not stop the
+    /// others. The outer error is for failures of the whole call; otherwise
+    /// there is one result per link, in order.
+    pub async fn store_many(&self, urls: &[model::Url]) -> Result<Vec<Result<(), Error>>, Error> {
+        // MongoDB rejects an `insert_many` without documents.
+        if urls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let start = Instant::now();
+
+        let outcome = self.collection.insert_many(urls).ordered(false).await;
+
+        metrics::observe_db_write(start.elapsed().as_secs_f64());
+
+        // `(0..n).map(|_| Ok(()))` starts with every document stored.
+        let mut results: Vec<Result<(), Error>> = (0..urls.len()).map(|_| Ok(())).collect();
+        let Err(err) = outcome else {
+            return Ok(results);
+        };
+        let ErrorKind::InsertMany(failure) = err.kind.as_ref() else {
+            return Err(Error::Database(Box::new(err)));
+        };
+        // A write concern error leaves it unknown what was written.
+        if failure.write_concern_error.is_some() {
+            return Err(Error::Database(Box::new(err)));
+        }
+        for write_error in failure.write_errors.iter().flatten() {
+            let Some(url) = urls.get(write_error.index) else {
+                continue;
+            };
+            results[write_error.index] = Err(if write_error.code == 11000 {
+                Error::DuplicateKey(url.key().to_string())
+            } else {
+                Error::Database(write_error.message.clone().into())
+            });
+        }
+        Ok(results)
+    }
+
     // `&model::Url` borrows the URL - we don't take ownership.
     // `Result<(), Error>` returns either success (unit type `()`) or an Error.
     pub async fn store(&self, url: &model::Url) -> Result<(), Error> {
@@ -0,0 +1,155 @@
+// Coarse `User-Agent` classification for redirect rules.
+// Only what rules can match on is extracted: operating system, device class
+// and whether the client is an automated agent. Substring checks are enough
+// for that and avoid shipping a full user-agent database.
+
+use crate::model::{Device, Os, Visitor};
+
+// Lowercase fragments that identify crawlers, link checkers and HTTP tools.
+const BOT_MARKERS: &[&str] = &[
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "facebookexternalhit",
+    "embedly",
+    "headlesschrome",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "go-http-client",
+];
+
+// Lowercase fragments of the crawlers chat and social apps send to unfurl
+// pasted links. iMessage identifies as both Facebook's and Twitter's bot.
+const UNFURL_MARKERS: &[&str] = &[
+    "facebookexternalhit",
+    "facebot",
+    "twitterbot",
+    "slackbot",
+    "discordbot",
+    "telegrambot",
+    "whatsapp",
+    "linkedinbot",
+    "skypeuripreview",
+    "mattermost",
+    "mastodon",
+    "embedly",
+    "pinterest",
+    "redditbot",
+    "vkshare",
+    "viber",
+];
+
+/// Whether `user_agent` belongs to a bot that builds link previews.
+pub fn is_unfurler(user_agent: &str) -> bool {
+    let ua = user_agent.to_lowercase();
+    UNFURL_MARKERS.iter().any(|m| ua.contains(m))
+}
+
+pub fn parse(user_agent: &str) -> Visitor {
+    let ua = user_agent.to_lowercase();
+
+    // Order matters: iOS agents claim to be "like Mac OS X", and Android
+    // agents contain "Linux".
+    let os = if ua.contains("iphone") || ua.contains("ipad") || ua.contains("ipod") {
+        Os::Ios
+    } else if ua.contains("android") {
+        Os::Android
+    } else if ua.contains("windows") {
+        Os::Windows
+    } else if ua.contains("macintosh") || ua.contains("mac os x") {
+        Os::Macos
+    } else if ua.contains("linux") {
+        Os::Linux
+    } else {
+        Os::Other
+    };
+
+    // Android tablets leave "Mobile" out of their user agent.
+    let device = if ua.contains("ipad")
+        || ua.contains("tablet")
+        || (os == Os::Android && !ua.contains("mobile"))
+    {
+        Device::Tablet
+    } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("ipod") {
+        Device::Mobile
+    } else {
+        Device::Desktop
+    };
+
+    Visitor {
+        os,
+        device,
+        // An empty user agent is almost never a real browser.
+        bot: ua.is_empty() || BOT_MARKERS.iter().any(|m| ua.contains(m)),
+        // Filled in separately from the client address.
+        country: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+    const IPAD: &str = "Mozilla/5.0 (iPad; CPU OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+    const ANDROID_PHONE: &str = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Mobile Safari/537.36";
+    const ANDROID_TABLET: &str = "Mozilla/5.0 (Linux; Android 13; SM-X710) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36";
+    const WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36";
+    const MAC: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_0) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Safari/605.1.15";
+    const LINUX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0";
+    const GOOGLEBOT: &str =
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+    #[test]
+    fn detects_ios() {
+        assert_eq!(
+            parse(IPHONE),
+            Visitor {
+                os: Os::Ios,
+                device: Device::Mobile,
+                bot: false,
+                country: None,
+            }
+        );
+        assert_eq!(parse(IPAD).device, Device::Tablet);
+        assert_eq!(parse(IPAD).os, Os::Ios);
+    }
+
+    #[test]
+    fn detects_android() {
+        assert_eq!(parse(ANDROID_PHONE).os, Os::Android);
+        assert_eq!(parse(ANDROID_PHONE).device, Device::Mobile);
+        assert_eq!(parse(ANDROID_TABLET).device, Device::Tablet);
+    }
+
+    #[test]
+    fn detects_desktops() {
+        assert_eq!(parse(WINDOWS).os, Os::Windows);
+        assert_eq!(parse(MAC).os, Os::Macos);
+        assert_eq!(parse(LINUX).os, Os::Linux);
+        for ua in [WINDOWS, MAC, LINUX] {
+            assert_eq!(parse(ua).device, Device::Desktop);
+            assert!(!parse(ua).bot);
+        }
+    }
+
+    #[test]
+    fn detects_bots() {
+        assert!(parse(GOOGLEBOT).bot);
+        assert!(parse("curl/8.4.0").bot);
+        assert!(parse("").bot);
+    }
+
+    #[test]
+    fn detects_unfurlers() {
+        assert!(is_unfurler(
+            "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)"
+        ));
+        assert!(is_unfurler("TelegramBot (like TwitterBot)"));
+        assert!(is_unfurler("WhatsApp/2.23.20.0"));
+        assert!(!is_unfurler(GOOGLEBOT));
+        assert!(!is_unfurler(IPHONE));
+    }
+}